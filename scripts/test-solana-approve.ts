@@ -175,6 +175,7 @@ async function main() {
       false, // not private
       1000, // volatility
       new anchor.BN(1800), // max time
+      3, // accepts SOL and USDC
     )
     .accounts({
       desk: DESK,
@@ -211,6 +212,7 @@ async function main() {
       0, // currency (0 = SOL)
       new anchor.BN(180 * 86400), // lockup_secs
       25, // agent_commission_bps (0.25%)
+      [], // allowlist_proof (public consignment)
     )
     .accounts({
       desk: DESK,
@@ -252,7 +254,6 @@ async function main() {
       desk: DESK,
       offer: offerKeypair.publicKey,
       deskTokenTreasury: deskTreasury,
-      agent: deskAccount.agent, // Agent receives commission
       deskSigner: deskKeypair.publicKey, // Desk keypair signs
       payer: wallet.publicKey,
       systemProgram: SystemProgram.programId,
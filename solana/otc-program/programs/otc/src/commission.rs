@@ -0,0 +1,150 @@
+//! Desk commission policy for negotiated consignment offers.
+//!
+//! Schedule mode sums a discount component and a lockup component, each interpolated linearly
+//! between configured points and held flat beyond the first/last point, then clamps the total
+//! to [min_bps, max_bps]. The EVM `calculateAgentCommission` is the schedule
+//! discount (500 => 100, 3000 => 25), lockup (0 => 0, 365 => 50), bounds 25-150.
+
+use anchor_lang::prelude::*;
+
+use crate::OtcError;
+
+pub const MAX_COMMISSION_POINTS: usize = 8;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum CommissionMode {
+    /// Always flat_bps
+    #[default]
+    Flat,
+    /// Caller-supplied bps, must lie within [min_bps, max_bps]
+    Bounded,
+    /// Derived from discount and lockup points, clamped to [min_bps, max_bps]
+    Schedule,
+}
+
+/// One schedule point: `at` is discount bps (discount points) or lockup days (lockup points)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct CommissionPoint {
+    pub at: u16,
+    pub bps: u16,
+}
+
+#[account]
+pub struct CommissionPolicy {
+    pub desk: Pubkey,
+    pub mode: CommissionMode,
+    pub flat_bps: u16,
+    pub min_bps: u16,
+    pub max_bps: u16,
+    pub discount_points: Vec<CommissionPoint>, // max 8, ascending by discount bps
+    pub lockup_points: Vec<CommissionPoint>, // max 8, ascending by lockup days
+}
+
+impl CommissionPolicy {
+    pub const SIZE: usize = 32+1+2+2+2+4+(4*MAX_COMMISSION_POINTS)+4+(4*MAX_COMMISSION_POINTS);
+
+    pub fn validate(&self) -> Result<()> {
+        require!(self.flat_bps <= 10_000, OtcError::CommissionRange);
+        require!(self.min_bps <= self.max_bps && self.max_bps <= 10_000, OtcError::CommissionRange);
+        validate_points(&self.discount_points)?;
+        validate_points(&self.lockup_points)
+    }
+
+    /// Commission for a negotiated deal; requested_bps is only honoured in Bounded mode
+    pub fn commission_bps(&self, requested_bps: u16, discount_bps: u16, lockup_days: u64) -> Result<u16> {
+        match self.mode {
+            CommissionMode::Flat => Ok(self.flat_bps),
+            CommissionMode::Bounded => {
+                require!(requested_bps >= self.min_bps && requested_bps <= self.max_bps, OtcError::CommissionRange);
+                Ok(requested_bps)
+            }
+            CommissionMode::Schedule => {
+                let lockup_days = u16::try_from(lockup_days).unwrap_or(u16::MAX);
+                let total = interpolate(&self.discount_points, discount_bps) as u32 + interpolate(&self.lockup_points, lockup_days) as u32;
+                Ok(total.clamp(self.min_bps as u32, self.max_bps as u32) as u16)
+            }
+        }
+    }
+}
+
+fn validate_points(points: &[CommissionPoint]) -> Result<()> {
+    require!(points.len() <= MAX_COMMISSION_POINTS, OtcError::CommissionRange);
+    require!(points.iter().all(|p| p.bps <= 10_000), OtcError::CommissionRange);
+    require!(points.windows(2).all(|w| w[0].at < w[1].at), OtcError::CommissionRange);
+    Ok(())
+}
+
+/// Piecewise-linear lookup, rounding the change from the lower point down
+fn interpolate(points: &[CommissionPoint], x: u16) -> u16 {
+    let (Some(first), Some(last)) = (points.first(), points.last()) else { return 0 };
+    if x <= first.at {
+        return first.bps;
+    }
+    if x >= last.at {
+        return last.bps;
+    }
+    let i = points.iter().position(|p| p.at > x).unwrap_or(points.len() - 1);
+    let (lo, hi) = (points[i - 1], points[i]);
+    let dx = (x - lo.at) as u32;
+    let span = (hi.at - lo.at) as u32;
+    if hi.bps >= lo.bps {
+        lo.bps + (dx * (hi.bps - lo.bps) as u32 / span) as u16
+    } else {
+        lo.bps - (dx * (lo.bps - hi.bps) as u32 / span) as u16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evm_policy() -> CommissionPolicy {
+        CommissionPolicy {
+            desk: Pubkey::default(),
+            mode: CommissionMode::Schedule,
+            flat_bps: 0,
+            min_bps: 25,
+            max_bps: 150,
+            discount_points: vec![CommissionPoint { at: 500, bps: 100 }, CommissionPoint { at: 3000, bps: 25 }],
+            lockup_points: vec![CommissionPoint { at: 0, bps: 0 }, CommissionPoint { at: 365, bps: 50 }],
+        }
+    }
+
+    // OTC.sol calculateAgentCommission
+    fn evm_commission(discount_bps: u64, lockup_days: u64) -> u16 {
+        let discount = if discount_bps <= 500 { 100 } else if discount_bps >= 3000 { 25 } else { 100 - ((discount_bps - 500) * 75) / 2500 };
+        let lockup = if lockup_days >= 365 { 50 } else { (lockup_days * 50) / 365 };
+        (discount + lockup).clamp(25, 150) as u16
+    }
+
+    #[test]
+    fn schedule_matches_evm_contract() {
+        let policy = evm_policy();
+        policy.validate().unwrap();
+        for discount in (0..=4000u16).step_by(37) {
+            for lockup in [0u64, 1, 30, 90, 180, 364, 365, 1000] {
+                assert_eq!(policy.commission_bps(0, discount, lockup).unwrap(), evm_commission(discount as u64, lockup), "{discount} {lockup}");
+            }
+        }
+    }
+
+    #[test]
+    fn flat_and_bounded_modes() {
+        let mut policy = evm_policy();
+        policy.mode = CommissionMode::Flat;
+        policy.flat_bps = 40;
+        assert_eq!(policy.commission_bps(150, 1000, 30).unwrap(), 40);
+
+        policy.mode = CommissionMode::Bounded;
+        assert_eq!(policy.commission_bps(150, 1000, 30).unwrap(), 150);
+        assert!(policy.commission_bps(151, 1000, 30).is_err());
+        assert!(policy.commission_bps(24, 1000, 30).is_err());
+    }
+
+    #[test]
+    fn rejects_unsorted_points() {
+        let mut policy = evm_policy();
+        policy.discount_points.reverse();
+        assert!(policy.validate().is_err());
+    }
+}
@@ -4,10 +4,20 @@
 #![allow(clippy::too_many_arguments)]
 #![allow(unexpected_cfgs)]
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{ed25519_program, keccak, sysvar::instructions as sysvar_instructions};
 use anchor_spl::token_interface::{
     Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked,
 };
-use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+use pyth_solana_receiver_sdk::price_update::{PriceUpdateV2, VerificationLevel};
+
+mod commission;
+mod pyth;
+mod settlement;
+pub use commission::{CommissionMode, CommissionPoint, CommissionPolicy};
+use pyth::{select_price_8d, PythQuote};
+use settlement::{
+    calc_discounted_usd, check_fulfillable, check_reserve_price, mul_div_ceil_u128, pow10, safe_u128_to_u64, settle, PaymentCurrency, Settlement, SettlementTerms,
+};
 
 declare_id!("3uTdWzoAcBFKTVYRd2z2jDKAcuyW64rQLxa9wMreDJKo");
 
@@ -24,6 +34,12 @@ pub struct OfferCreated {
 #[event]
 pub struct OfferApproved { pub offer: Pubkey, pub approver: Pubkey }
 
+#[event]
+pub struct OfferCountered { pub offer: Pubkey, pub by: Pubkey, pub round: u16, pub token_amount: u64, pub discount_bps: u16, pub lockup_secs: i64 }
+
+#[event]
+pub struct OfferCounterResponse { pub offer: Pubkey, pub round: u16, pub accepted: bool }
+
 #[event]
 pub struct OfferCancelled { pub offer: Pubkey, pub by: Pubkey }
 
@@ -31,16 +47,157 @@ pub struct OfferCancelled { pub offer: Pubkey, pub by: Pubkey }
 pub struct OfferPaid { pub offer: Pubkey, pub payer: Pubkey, pub amount: u64, pub currency: u8 }
 
 #[event]
-pub struct AgentCommissionPaid { pub offer: Pubkey, pub agent: Pubkey, pub amount: u64, pub currency: u8 }
+pub struct FeeAccrued { pub offer: Pubkey, pub recipient: Pubkey, pub kind: FeeKind, pub amount: u64, pub currency: u8, pub accrued_amount: u64 }
+
+#[event]
+pub struct FeeReversed { pub offer: Pubkey, pub recipient: Pubkey, pub kind: FeeKind, pub amount: u64, pub currency: u8, pub accrued_amount: u64 }
+
+#[event]
+pub struct FeeClaimed { pub recipient: Pubkey, pub amount: u64, pub currency: u8 }
+
+#[event]
+pub struct FeeScheduleUpdated { pub protocol_treasury: Pubkey, pub protocol_fee_bps: u16, pub referrer_bps: u16 }
 
 #[event]
 pub struct TokensClaimed { pub offer: Pubkey, pub beneficiary: Pubkey, pub amount: u64 }
 
+#[event]
+pub struct ConsignmentCreated {
+    pub consignment: Pubkey,
+    pub id: u64,
+    pub consigner: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub is_negotiable: bool,
+    pub accepted_currencies: u8, // ACCEPT_* flags
+}
+
+#[event]
+pub struct ConsignmentTermsUpdated {
+    pub consignment: Pubkey,
+    pub fixed_discount_bps: u16,
+    pub fixed_lockup_days: u32,
+    pub min_discount_bps: u16,
+    pub max_discount_bps: u16,
+    pub min_lockup_days: u32,
+    pub max_lockup_days: u32,
+    pub min_deal_amount: u64,
+    pub max_deal_amount: u64,
+}
+
+#[event]
+pub struct ConsignmentToppedUp { pub consignment: Pubkey, pub amount: u64, pub total_amount: u64, pub remaining_amount: u64 }
+
+#[event]
+pub struct ConsignmentPartiallyWithdrawn { pub consignment: Pubkey, pub amount: u64, pub total_amount: u64, pub remaining_amount: u64 }
+
+#[event]
+pub struct ConsignmentExpirySet { pub consignment: Pubkey, pub expires_at: i64 }
+
+#[event]
+pub struct ConsignmentExpired { pub consignment: Pubkey, pub remaining_amount: u64, pub by: Pubkey }
+
+#[event]
+pub struct ConsignmentSwept { pub consignment: Pubkey, pub amount_returned: u64 }
+
+#[event]
+pub struct DutchAuctionSet { pub consignment: Pubkey, pub start_at: i64, pub end_at: i64, pub start_discount_bps: u16, pub end_discount_bps: u16 }
+
+#[event]
+pub struct DiscountTiersSet { pub consignment: Pubkey, pub basis: TierBasis, pub tiers: Vec<DiscountTier> }
+
+#[event]
+pub struct DiscountCurveSet { pub target: Pubkey, pub points: Vec<DiscountCurvePoint> } // target is the desk or a consignment
+
+#[event]
+pub struct ConsignmentReserveSet { pub consignment: Pubkey, pub min_net_price_usd_8d: u64 }
+
+#[event]
+pub struct ConsignmentFixedPriceSet { pub consignment: Pubkey, pub fixed_price_usd_8d: u64 }
+
+#[event]
+pub struct AuctionCreated { pub auction: Pubkey, pub consignment: Pubkey, pub token_amount: u64, pub deposit_amount: u64, pub commit_end_at: i64, pub reveal_end_at: i64 }
+
+#[event]
+pub struct BidCommitted { pub auction: Pubkey, pub bidder: Pubkey, pub deposit: u64 }
+
+#[event]
+pub struct BidRevealed { pub auction: Pubkey, pub bidder: Pubkey, pub discount_bps: u16, pub token_amount: u64 }
+
+#[event]
+pub struct AuctionBidAllocated { pub auction: Pubkey, pub bidder: Pubkey, pub discount_bps: u16, pub allocated_amount: u64 }
+
+#[event]
+pub struct AuctionSettled { pub auction: Pubkey, pub allocated_amount: u64, pub returned_amount: u64, pub winners: u32 }
+
+#[event]
+pub struct BidDepositReleased { pub auction: Pubkey, pub bidder: Pubkey, pub to: Pubkey, pub amount: u64, pub forfeited: bool, pub returned_amount: u64 }
+
+#[event]
+pub struct BidCreated {
+    pub bid: Pubkey,
+    pub buyer: Pubkey,
+    pub token_mint: Pubkey,
+    pub max_token_amount: u64,
+    pub discount_bps: u16,
+    pub lockup_secs: i64,
+    pub escrow_amount: u64,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct BidFilled { pub bid: Pubkey, pub offer: Pubkey, pub seller: Pubkey, pub token_amount: u64, pub payment: u64, pub seller_net: u64 }
+
+#[event]
+pub struct BidCancelled { pub bid: Pubkey, pub refunded: u64 }
+
+#[event]
+pub struct ConsignmentAllowlistUpdated { pub consignment: Pubkey, pub allowlist_root: [u8; 32] }
+
+#[event]
+pub struct ComplianceConfigUpdated { pub required_mask: u8, pub tier_max_usd_8d: [u64; COMPLIANCE_TIERS] }
+
+#[event]
+pub struct CommissionPolicyUpdated {
+    pub mode: CommissionMode,
+    pub flat_bps: u16,
+    pub min_bps: u16,
+    pub max_bps: u16,
+    pub discount_points: Vec<CommissionPoint>,
+    pub lockup_points: Vec<CommissionPoint>,
+}
+
+#[event]
+pub struct AttestationUpdated { pub wallet: Pubkey, pub tier: u8, pub expires_at: i64, pub denied: bool, pub issuer: Pubkey }
+
+#[event]
+pub struct BatchClaimResult { pub offer: Pubkey, pub beneficiary: Pubkey, pub amount: u64, pub status: ClaimStatus }
+
+#[event]
+pub struct OfferClosed { pub offer: Pubkey, pub rent_recipient: Pubkey, pub by: Pubkey }
+
 #[event]
 pub struct LimitsUpdated { pub min_usd_amount_8d: u64, pub max_token_per_order: u64, pub quote_expiry_secs: i64, pub default_unlock_delay_secs: i64, pub max_lockup_secs: i64 }
 
 #[event]
-pub struct PricesUpdated { pub token_usd_8d: u64, pub sol_usd_8d: u64, pub updated_at: i64, pub max_age: i64 }
+pub struct PricesUpdated {
+    pub token_usd_8d: u64,
+    pub sol_usd_8d: u64,
+    pub updated_at: i64,
+    pub max_age: i64,
+    // Levels the Pyth updates carried, None for manual prices
+    pub token_verification: Option<PythVerification>,
+    pub sol_verification: Option<PythVerification>,
+}
+
+#[event]
+pub struct TokenPriceUpdated { pub token_registry: Pubkey, pub token_usd_8d: u64, pub updated_at: i64, pub verification: PythVerification } // level the update carried
+
+#[event]
+pub struct PythVerificationSet { pub min_verification: PythVerification }
+
+#[event]
+pub struct PythPriceGuardSet { pub target: Pubkey, pub max_conf_bps: u16, pub mode: PythPriceMode } // target is the desk or a token registry
 
 #[event]
 pub struct RestrictFulfillUpdated { pub enabled: bool }
@@ -48,6 +205,9 @@ pub struct RestrictFulfillUpdated { pub enabled: bool }
 #[event]
 pub struct Paused { pub paused: bool }
 
+#[event]
+pub struct AccountMigrated { pub account: Pubkey, pub old_len: u64, pub new_len: u64, pub payer: Pubkey }
+
 #[allow(deprecated)]
 #[program]
 pub mod otc {
@@ -72,6 +232,7 @@ pub mod otc {
         desk.min_usd_amount_8d = min_usd_amount_8d;
         desk.quote_expiry_secs = quote_expiry_secs;
         desk.max_price_age_secs = 3600;
+        desk.min_pyth_verification = PythVerification::Full;
         desk.restrict_fulfill = false;
         desk.next_consignment_id = 1;
         desk.next_offer_id = 1;
@@ -94,6 +255,7 @@ pub mod otc {
         desk.emergency_refund_deadline_secs = 30 * 86400; // 30 days default
         desk.approvers = Vec::new();
         desk.p2p_commission_bps = 25; // Default: 0.25% commission for P2P deals
+        desk.compliance_required_mask = 0; // No attestation required until set_compliance_config
         Ok(())
     }
 
@@ -108,6 +270,71 @@ pub mod otc {
         Ok(())
     }
 
+    /// Grow a Desk, TokenRegistry, Consignment or Offer created before the current layout to its full size.
+    /// Permissionless: the payer funds the extra rent and the appended fields start at their defaults.
+    /// Run once per pre-upgrade account right after upgrading the program; those accounts fail to load until migrated.
+    pub fn migrate_account(ctx: Context<MigrateAccount>) -> Result<()> {
+        let info = ctx.accounts.account.to_account_info();
+        let old_len = info.data_len();
+        let (discriminator, legacy_end, new_len) = {
+            let data = info.try_borrow_data()?;
+            require!(old_len >= 8, OtcError::BadState);
+            let discriminator: [u8; 8] = data[..8].try_into().unwrap();
+            let (legacy_size, legacy_end, size) = if discriminator == Desk::DISCRIMINATOR {
+                // The approver list is the only variable-length legacy field; bytes past its serialized end may be stale
+                let at = 8 + DESK_LEGACY_APPROVERS_OFFSET;
+                require!(old_len >= at + 4, OtcError::BadState);
+                let approvers = u32::from_le_bytes(data[at..at + 4].try_into().unwrap()) as usize;
+                require!(approvers <= 32, OtcError::BadState);
+                (DESK_LEGACY_SIZE, DESK_LEGACY_SIZE - 32 * (32 - approvers), Desk::SIZE)
+            } else if discriminator == TokenRegistry::DISCRIMINATOR {
+                (TOKEN_REGISTRY_LEGACY_SIZE, TOKEN_REGISTRY_LEGACY_SIZE, TokenRegistry::SIZE)
+            } else if discriminator == Consignment::DISCRIMINATOR {
+                (CONSIGNMENT_LEGACY_SIZE, CONSIGNMENT_LEGACY_SIZE, Consignment::SIZE)
+            } else if discriminator == Offer::DISCRIMINATOR {
+                (OFFER_LEGACY_SIZE, OFFER_LEGACY_SIZE, Offer::SIZE)
+            } else {
+                return err!(OtcError::BadState);
+            };
+            require!(old_len == 8 + legacy_size, OtcError::BadState); // already migrated or not a legacy account
+            (discriminator, 8 + legacy_end, 8 + size)
+        };
+
+        let shortfall = Rent::get()?.minimum_balance(new_len).saturating_sub(info.lamports());
+        if shortfall > 0 {
+            let ix = anchor_lang::solana_program::system_instruction::transfer(&ctx.accounts.payer.key(), &info.key(), shortfall);
+            anchor_lang::solana_program::program::invoke(&ix, &[
+                ctx.accounts.payer.to_account_info(),
+                info.clone(),
+                ctx.accounts.system_program.to_account_info(),
+            ])?;
+        }
+        info.resize(new_len)?;
+
+        let mut data = info.try_borrow_mut_data()?;
+        data[legacy_end..].fill(0);
+        // Fields whose zero value would change the behaviour of an existing account
+        if discriminator == Desk::DISCRIMINATOR {
+            let mut desk = Desk::try_deserialize(&mut &data[..])?;
+            desk.min_pyth_verification = PythVerification::Full; // variant 0 is Partial { num_signatures: 0 }
+            desk.try_serialize(&mut &mut data[..])?;
+        } else if discriminator == Consignment::DISCRIMINATOR {
+            let mut consignment = Consignment::try_deserialize(&mut &data[..])?;
+            consignment.accepted_currencies = ACCEPT_SOL | ACCEPT_USDC;
+            // Legacy withdrawals only cleared is_active, so an inactive consignment is treated as withdrawn
+            // and restored inventory is never re-listed behind the consigner's back
+            consignment.is_withdrawn = !consignment.is_active;
+            consignment.try_serialize(&mut &mut data[..])?;
+        } else if discriminator == Offer::DISCRIMINATOR {
+            let mut offer = Offer::try_deserialize(&mut &data[..])?;
+            offer.rent_payer = offer.beneficiary;
+            offer.try_serialize(&mut &mut data[..])?;
+        }
+
+        emit!(AccountMigrated { account: info.key(), old_len: old_len as u64, new_len: new_len as u64, payer: ctx.accounts.payer.key() });
+        Ok(())
+    }
+
     pub fn register_token(
         ctx: Context<RegisterToken>,
         price_feed_id: [u8; 32],
@@ -141,10 +368,13 @@ pub mod otc {
         registry.twap_last_price = 0;
         registry.max_twap_deviation_bps = 0; // Disabled by default
         registry.min_update_interval_secs = 60; // Minimum 1 minute between updates
+        registry.pyth_max_conf_bps = 0; // No confidence limit until set_token_pyth_price_guard
+        registry.pyth_price_mode = PythPriceMode::Spot;
         
         Ok(())
     }
 
+    /// accepted_currencies: ACCEPT_SOL and/or ACCEPT_USDC, the currencies offers on this consignment may be paid in
     pub fn create_consignment(
         ctx: Context<CreateConsignment>,
         amount: u64,
@@ -161,15 +391,16 @@ pub mod otc {
         is_private: bool,
         max_price_volatility_bps: u16,
         max_time_to_execute_secs: i64,
+        accepted_currencies: u8,
     ) -> Result<()> {
         let desk = &mut ctx.accounts.desk;
         require!(!desk.paused, OtcError::Paused);
         require!(amount > 0, OtcError::AmountRange);
-        require!(min_deal_amount <= max_deal_amount, OtcError::AmountRange);
-        require!(min_discount_bps <= max_discount_bps, OtcError::Discount);
-        require!(max_discount_bps <= 10000, OtcError::Discount); // Max 100% discount
-        require!(fixed_discount_bps <= 10000, OtcError::Discount); // Max 100% discount
-        require!(min_lockup_days <= max_lockup_days, OtcError::LockupTooLong);
+        require!(accepted_currencies != 0 && accepted_currencies & !(ACCEPT_SOL | ACCEPT_USDC) == 0, OtcError::UnsupportedCurrency);
+        validate_consignment_terms(fixed_discount_bps, min_discount_bps, max_discount_bps, min_lockup_days, max_lockup_days, min_deal_amount, max_deal_amount)?;
+        check_block_deal_bounds(is_fractionalized, amount, min_deal_amount, max_deal_amount)?;
+        // 0 = use desk.quote_expiry_secs, otherwise same 60s floor as the desk setting
+        require!(max_time_to_execute_secs == 0 || max_time_to_execute_secs >= 60, OtcError::ExecuteWindow);
 
         let cpi_accounts = TransferChecked {
             from: ctx.accounts.consigner_token_ata.to_account_info(),
@@ -205,7 +436,567 @@ pub mod otc {
         consignment.max_time_to_execute_secs = max_time_to_execute_secs;
         consignment.is_active = true;
         consignment.created_at = Clock::get()?.unix_timestamp;
+        consignment.expires_at = 0; // Never expires until set_consignment_expiry
+        consignment.allowlist_root = [0u8; 32];
+        consignment.auction_start_at = 0;
+        consignment.auction_end_at = 0;
+        consignment.tier_basis = TierBasis::TokenAmount;
+        consignment.discount_tiers = Vec::new();
+        consignment.discount_curve = Vec::new();
+        consignment.min_net_price_usd_8d = 0;
+        consignment.fixed_price_usd_8d = 0;
+        consignment.accepted_currencies = accepted_currencies;
+        consignment.is_withdrawn = false;
+        consignment.auction_live = false;
+
+        emit!(ConsignmentCreated {
+            consignment: ctx.accounts.consignment.key(),
+            id: consignment_id,
+            consigner: ctx.accounts.consigner.key(),
+            token_mint: ctx.accounts.token_mint.key(),
+            amount,
+            is_negotiable,
+            accepted_currencies,
+        });
+        Ok(())
+    }
+
+    /// Re-price an existing consignment (consigner only)
+    /// Offers already created keep the discount, lockup and commission captured at creation
+    pub fn update_consignment(
+        ctx: Context<UpdateConsignment>,
+        _consignment_id: u64,
+        fixed_discount_bps: u16,
+        fixed_lockup_days: u32,
+        min_discount_bps: u16,
+        max_discount_bps: u16,
+        min_lockup_days: u32,
+        max_lockup_days: u32,
+        min_deal_amount: u64,
+        max_deal_amount: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.desk.paused, OtcError::Paused);
+        validate_consignment_terms(fixed_discount_bps, min_discount_bps, max_discount_bps, min_lockup_days, max_lockup_days, min_deal_amount, max_deal_amount)?;
+
+        let consignment_key = ctx.accounts.consignment.key();
+        let consignment = &mut ctx.accounts.consignment;
+        require!(consignment.is_active, OtcError::BadState);
+        // Settlement reads the deal bounds of the consignment, so they are frozen while an auction runs
+        require!(!consignment.auction_live, OtcError::AuctionPhase);
+        check_block_deal_bounds(consignment.is_fractionalized, consignment.remaining_amount, min_deal_amount, max_deal_amount)?;
+        consignment.fixed_discount_bps = fixed_discount_bps;
+        consignment.fixed_lockup_days = fixed_lockup_days;
+        consignment.min_discount_bps = min_discount_bps;
+        consignment.max_discount_bps = max_discount_bps;
+        consignment.min_lockup_days = min_lockup_days;
+        consignment.max_lockup_days = max_lockup_days;
+        consignment.min_deal_amount = min_deal_amount;
+        consignment.max_deal_amount = max_deal_amount;
+
+        emit!(ConsignmentTermsUpdated {
+            consignment: consignment_key,
+            fixed_discount_bps,
+            fixed_lockup_days,
+            min_discount_bps,
+            max_discount_bps,
+            min_lockup_days,
+            max_lockup_days,
+            min_deal_amount,
+            max_deal_amount,
+        });
+        Ok(())
+    }
+
+    /// Add inventory to an existing consignment (consigner only)
+    /// Re-activates a sold-out consignment; withdrawn consignments stay closed
+    pub fn top_up_consignment(ctx: Context<TopUpConsignment>, _consignment_id: u64, amount: u64) -> Result<()> {
+        require!(!ctx.accounts.desk.paused, OtcError::Paused);
+        require!(amount > 0, OtcError::AmountRange);
+        require!(!is_consignment_expired(&ctx.accounts.consignment, Clock::get()?.unix_timestamp), OtcError::ConsignmentExpired);
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.consigner_token_ata.to_account_info(),
+            to: ctx.accounts.desk_token_treasury.to_account_info(),
+            authority: ctx.accounts.consigner.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        transfer_checked(cpi_ctx, amount, ctx.accounts.token_mint.decimals)?;
+
+        let consignment_key = ctx.accounts.consignment.key();
+        let consignment = &mut ctx.accounts.consignment;
+        require!(!consignment.is_withdrawn, OtcError::BadState);
+        consignment.total_amount = consignment.total_amount.checked_add(amount).ok_or(OtcError::Overflow)?;
+        consignment.remaining_amount = consignment.remaining_amount.checked_add(amount).ok_or(OtcError::Overflow)?;
+        check_block_deal_bounds(consignment.is_fractionalized, consignment.remaining_amount, consignment.min_deal_amount, consignment.max_deal_amount)?;
+        consignment.is_active = true;
+
+        emit!(ConsignmentToppedUp {
+            consignment: consignment_key,
+            amount,
+            total_amount: consignment.total_amount,
+            remaining_amount: consignment.remaining_amount,
+        });
+        Ok(())
+    }
+
+    /// Set or clear (0) the time after which the consignment stops accepting offers (consigner only)
+    pub fn set_consignment_expiry(ctx: Context<UpdateConsignment>, _consignment_id: u64, expires_at: i64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(expires_at == 0 || expires_at > now, OtcError::AmountRange);
+
+        let consignment_key = ctx.accounts.consignment.key();
+        let consignment = &mut ctx.accounts.consignment;
+        require!(consignment.is_active, OtcError::BadState);
+        require!(!is_consignment_expired(consignment, now), OtcError::ConsignmentExpired);
+        consignment.expires_at = expires_at;
+
+        emit!(ConsignmentExpirySet { consignment: consignment_key, expires_at });
+        Ok(())
+    }
+
+    /// Turn a P2P consignment into a Dutch auction, or back to its fixed discount with (0, 0) (consigner only)
+    /// The discount moves linearly from min_discount_bps at start_at to max_discount_bps at end_at
+    /// and stays at the nearest end outside the window
+    pub fn set_dutch_auction(ctx: Context<UpdateConsignment>, _consignment_id: u64, start_at: i64, end_at: i64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!((start_at == 0 && end_at == 0) || (start_at < end_at && end_at > now), OtcError::AmountRange);
+
+        let consignment_key = ctx.accounts.consignment.key();
+        let consignment = &mut ctx.accounts.consignment;
+        require!(!consignment.is_negotiable, OtcError::NegotiableConsignment);
+        require!(consignment.is_active, OtcError::BadState);
+        require!(consignment.discount_tiers.is_empty(), OtcError::BadState);
+        consignment.auction_start_at = start_at;
+        consignment.auction_end_at = end_at;
+
+        emit!(DutchAuctionSet {
+            consignment: consignment_key,
+            start_at,
+            end_at,
+            start_discount_bps: consignment.min_discount_bps,
+            end_discount_bps: consignment.max_discount_bps,
+        });
+        Ok(())
+    }
+
+    /// Set or clear (empty tiers) a size-tiered discount schedule (consigner only)
+    /// The tier with the largest min_size not above the order size applies; orders below the first tier are rejected
+    /// Negotiable offers must pick a discount inside the tier's band, P2P tiers carry one discount (min == max)
+    pub fn set_discount_tiers(ctx: Context<UpdateConsignment>, _consignment_id: u64, basis: TierBasis, tiers: Vec<DiscountTier>) -> Result<()> {
+        require!(tiers.len() <= MAX_DISCOUNT_TIERS, OtcError::AmountRange);
+        require!(tiers.windows(2).all(|w| w[0].min_size < w[1].min_size), OtcError::AmountRange);
+        let consignment_key = ctx.accounts.consignment.key();
+        let consignment = &mut ctx.accounts.consignment;
+        require!(consignment.is_active, OtcError::BadState);
+        require!(tiers.is_empty() || !is_dutch_auction(consignment), OtcError::BadState);
+        for tier in &tiers {
+            require!(tier.min_discount_bps <= tier.max_discount_bps && tier.max_discount_bps <= 10000, OtcError::Discount);
+            require!(consignment.is_negotiable || tier.min_discount_bps == tier.max_discount_bps, OtcError::Discount);
+        }
+        consignment.tier_basis = basis;
+        consignment.discount_tiers = tiers.clone();
+
+        emit!(DiscountTiersSet { consignment: consignment_key, basis, tiers });
+        Ok(())
+    }
+
+    /// Set or clear (no points) the consignment's maximum discount by lockup days curve (consigner only)
+    /// Points ascend by lockup_days; the maximum is interpolated linearly and held flat past the ends
+    pub fn set_consignment_discount_curve(ctx: Context<UpdateConsignment>, _consignment_id: u64, points: Vec<DiscountCurvePoint>) -> Result<()> {
+        validate_discount_curve(&points)?;
+        let consignment_key = ctx.accounts.consignment.key();
+        let consignment = &mut ctx.accounts.consignment;
+        require!(consignment.is_active, OtcError::BadState);
+        consignment.discount_curve = points.clone();
+        emit!(DiscountCurveSet { target: consignment_key, points });
+        Ok(())
+    }
+
+    /// Set or clear (0) the lowest discounted per-token USD price the consignment sells at (consigner only)
+    /// Checked when offers are created or re-quoted and again at fulfillment
+    pub fn set_consignment_reserve(ctx: Context<UpdateConsignment>, _consignment_id: u64, min_net_price_usd_8d: u64) -> Result<()> {
+        let consignment_key = ctx.accounts.consignment.key();
+        let consignment = &mut ctx.accounts.consignment;
+        require!(consignment.is_active, OtcError::BadState);
+        consignment.min_net_price_usd_8d = min_net_price_usd_8d;
+        emit!(ConsignmentReserveSet { consignment: consignment_key, min_net_price_usd_8d });
+        Ok(())
+    }
+
+    /// Price the consignment at a fixed USD price per token instead of the registry price (consigner only)
+    /// 0 returns to registry pricing; min_usd_amount_8d still applies
+    pub fn set_consignment_fixed_price(ctx: Context<UpdateConsignment>, _consignment_id: u64, fixed_price_usd_8d: u64) -> Result<()> {
+        let consignment_key = ctx.accounts.consignment.key();
+        let consignment = &mut ctx.accounts.consignment;
+        require!(consignment.is_active, OtcError::BadState);
+        consignment.fixed_price_usd_8d = fixed_price_usd_8d;
+        emit!(ConsignmentFixedPriceSet { consignment: consignment_key, fixed_price_usd_8d });
+        Ok(())
+    }
+
+    /// Set or clear (zero root) the merkle allowlist of beneficiaries for a private consignment (consigner only)
+    /// Leaves are keccak256(beneficiary pubkey), pairs are hashed sorted
+    pub fn set_consignment_allowlist(ctx: Context<UpdateConsignment>, _consignment_id: u64, allowlist_root: [u8; 32]) -> Result<()> {
+        let consignment_key = ctx.accounts.consignment.key();
+        let consignment = &mut ctx.accounts.consignment;
+        require!(consignment.is_active, OtcError::BadState);
+        require!(consignment.is_private, OtcError::BadState);
+        consignment.allowlist_root = allowlist_root;
+
+        emit!(ConsignmentAllowlistUpdated { consignment: consignment_key, allowlist_root });
+        Ok(())
+    }
+
+    /// Start a sealed-bid auction for a consignment's remaining inventory (consigner only)
+    /// Bidders commit keccak256(discount_bps le || token_amount le || salt || bidder) with a USDC deposit of at
+    /// least deposit_amount before commit_end_at and reveal before reveal_end_at; lowest discounts are filled first
+    /// A revealed bid's deposit must cover deposit_bps of its discounted value, and winners have claim_window_secs
+    /// after settlement to claim their offer
+    pub fn create_auction(
+        ctx: Context<CreateAuction>,
+        _consignment_id: u64,
+        commit_end_at: i64,
+        reveal_end_at: i64,
+        deposit_amount: u64,
+        deposit_bps: u16,
+        claim_window_secs: i64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.desk.paused, OtcError::Paused);
+        let now = Clock::get()?.unix_timestamp;
+        require!(now < commit_end_at && commit_end_at < reveal_end_at, OtcError::AmountRange);
+        // A free commitment could be spammed at no cost, so every bid escrows something
+        require!(deposit_amount > 0 && deposit_bps <= 10000 && claim_window_secs > 0, OtcError::AmountRange);
+
+        let consignment_key = ctx.accounts.consignment.key();
+        let consignment = &mut ctx.accounts.consignment;
+        require!(consignment.is_active && consignment.remaining_amount > 0, OtcError::BadState);
+        require!(!is_consignment_expired(consignment, now), OtcError::ConsignmentExpired);
+        require!(!is_dutch_auction(consignment), OtcError::BadState);
+        require!(accepts_currency(consignment, 1), OtcError::CurrencyNotAccepted); // auction offers settle in USDC
+
+        // Inventory is reserved for the auction; settle_auction returns whatever is not allocated
+        let token_amount = consignment.remaining_amount;
+        consignment.remaining_amount = 0;
+        consignment.auction_live = true;
+
+        let auction_key = ctx.accounts.auction.key();
+        let auction = &mut ctx.accounts.auction;
+        auction.desk = ctx.accounts.desk.key();
+        auction.consignment = consignment_key;
+        auction.token_amount = token_amount;
+        auction.max_discount_bps = consignment.max_discount_bps;
+        auction.deposit_amount = deposit_amount;
+        auction.commit_end_at = commit_end_at;
+        auction.reveal_end_at = reveal_end_at;
+        auction.bid_count = 0;
+        auction.revealed_count = 0;
+        auction.allocated_amount = 0;
+        auction.settled = false;
+        auction.deposit_bps = deposit_bps;
+        auction.claim_window_secs = claim_window_secs;
+        auction.claim_end_at = 0;
+        auction.clearing_price_8d = 0;
+        auction.bids = Vec::new();
+
+        emit!(AuctionCreated { auction: auction_key, consignment: consignment_key, token_amount, deposit_amount, commit_end_at, reveal_end_at });
+        Ok(())
+    }
+
+    /// Commit a sealed bid and escrow its USDC deposit (at least auction.deposit_amount) in the desk treasury
+    /// An auction takes at most MAX_AUCTION_BIDS bids
+    pub fn commit_bid(ctx: Context<CommitBid>, commitment: [u8; 32], deposit: u64) -> Result<()> {
+        require!(!ctx.accounts.desk.paused, OtcError::Paused);
+        let now = Clock::get()?.unix_timestamp;
+        let auction_key = ctx.accounts.auction.key();
+        let bidder_key = ctx.accounts.bidder.key();
+        let auction = &mut ctx.accounts.auction;
+        require!(now < auction.commit_end_at, OtcError::AuctionPhase);
+        require!((auction.bid_count as usize) < MAX_AUCTION_BIDS, OtcError::AuctionFull);
+        require!(deposit >= auction.deposit_amount, OtcError::DepositTooLow);
+        // Sealed bids carry no USD value yet; the tier cap is applied when the winner claims its offer
+        check_compliance(
+            &ctx.accounts.desk,
+            COMPLIANCE_CREATE_OFFER_FROM_CONSIGNMENT,
+            ctx.accounts.compliance_config.as_deref(),
+            &ctx.accounts.bidder_attestation,
+            0,
+            now,
+        )?;
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.bidder_usdc_ata.to_account_info(),
+            to: ctx.accounts.desk_usdc_treasury.to_account_info(),
+            authority: ctx.accounts.bidder.to_account_info(),
+            mint: ctx.accounts.usdc_mint.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        transfer_checked(cpi_ctx, deposit, ctx.accounts.desk.usdc_decimals)?;
+        let desk = &mut ctx.accounts.desk;
+        desk.escrowed_usdc = desk.escrowed_usdc.checked_add(deposit).ok_or(OtcError::Overflow)?;
+
+        let bid = &mut ctx.accounts.sealed_bid;
+        bid.auction = auction_key;
+        bid.bidder = bidder_key;
+        bid.commitment = commitment;
+        bid.deposit = deposit;
+        bid.bid_index = auction.bid_count;
+        bid.revealed = false;
+        bid.discount_bps = 0;
+        bid.token_amount = 0;
+        bid.allocated_amount = 0;
+        bid.offer_created = false;
+        bid.offer = Pubkey::default();
+        auction.bid_count = auction.bid_count.checked_add(1).ok_or(OtcError::Overflow)?;
+
+        emit!(BidCommitted { auction: auction_key, bidder: bidder_key, deposit });
+        Ok(())
+    }
+
+    /// Reveal a committed bid between commit_end_at and reveal_end_at; the deposit must cover
+    /// auction.deposit_bps of the bid's discounted value at the current consignment price
+    /// Revealed bids are kept on the auction in allocation order, unrevealed bids forfeit their deposit to the consigner
+    pub fn reveal_bid(ctx: Context<RevealBid>, discount_bps: u16, token_amount: u64, salt: [u8; 32]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let auction_key = ctx.accounts.auction.key();
+        let bidder_key = ctx.accounts.bidder.key();
+        let auction = &mut ctx.accounts.auction;
+        require!(now >= auction.commit_end_at && now < auction.reveal_end_at, OtcError::AuctionPhase);
+
+        let bid = &mut ctx.accounts.sealed_bid;
+        require!(!bid.revealed, OtcError::BadState);
+        let hash = keccak::hashv(&[&discount_bps.to_le_bytes(), &token_amount.to_le_bytes(), &salt, bidder_key.as_ref()]).0;
+        require!(hash == bid.commitment, OtcError::InvalidReveal);
+        require!(discount_bps <= auction.max_discount_bps, OtcError::Discount);
+        require!(token_amount > 0, OtcError::AmountRange);
+
+        // Deposit sizing only needs a rough value, so a stale registry price does not block reveals
+        let consignment = &ctx.accounts.consignment;
+        let registry = &ctx.accounts.token_registry;
+        let price_8d = if consignment.fixed_price_usd_8d > 0 { consignment.fixed_price_usd_8d } else { registry.token_usd_price_8d };
+        let usd_8d = calc_discounted_usd(token_amount, price_8d, registry.decimals, discount_bps)?;
+        let required = auction_deposit_required(usd_8d, auction.deposit_bps, ctx.accounts.desk.usdc_decimals)?;
+        require!(bid.deposit >= required, OtcError::DepositTooLow);
+
+        bid.revealed = true;
+        bid.discount_bps = discount_bps;
+        bid.token_amount = token_amount;
+        let pos = auction.bids.partition_point(|b| (b.discount_bps, b.bid_index) < (discount_bps, bid.bid_index));
+        auction.bids.insert(pos, RevealedBid { bidder: bidder_key, bid_index: bid.bid_index, discount_bps, token_amount, allocated_amount: 0 });
+        auction.revealed_count = auction.revealed_count.checked_add(1).ok_or(OtcError::Overflow)?;
+
+        emit!(BidRevealed { auction: auction_key, bidder: bidder_key, discount_bps, token_amount });
+        Ok(())
+    }
+
+    /// Permissionless crank after reveal_end_at: lock the consignment price as the clearing price and allocate the
+    /// auctioned amount to the revealed bids, lowest discount first (earlier commitments win ties)
+    /// Bids that would clear under the consignment reserve get nothing; the rest returns to the consignment
+    pub fn settle_auction(ctx: Context<SettleAuction>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let auction_key = ctx.accounts.auction.key();
+        require!(now >= ctx.accounts.auction.reveal_end_at && !ctx.accounts.auction.settled, OtcError::AuctionPhase);
+        let price_8d = consignment_price_8d(&ctx.accounts.desk, &ctx.accounts.consignment, &ctx.accounts.token_registry, now)?;
+
+        let auction = &mut ctx.accounts.auction;
+        let consignment = &mut ctx.accounts.consignment;
+        let total = auction.token_amount;
+        let mut left = total;
+        let mut winners = 0u32;
+        for bid in auction.bids.iter_mut() {
+            let fill = if check_reserve_price(consignment.min_net_price_usd_8d, price_8d, bid.discount_bps).is_err() {
+                0
+            } else if consignment.is_fractionalized {
+                let fill = bid.token_amount.min(consignment.max_deal_amount).min(left);
+                if fill >= consignment.min_deal_amount { fill } else { 0 }
+            } else if left == total && bid.token_amount >= left {
+                left // Non-fractionalized consignments sell as a single block
+            } else {
+                0
+            };
+            if fill > 0 {
+                winners += 1;
+                left -= fill;
+            }
+            bid.allocated_amount = fill;
+            emit!(AuctionBidAllocated { auction: auction_key, bidder: bid.bidder, discount_bps: bid.discount_bps, allocated_amount: fill });
+        }
+
+        auction.allocated_amount = total - left;
+        auction.settled = true;
+        auction.clearing_price_8d = price_8d;
+        auction.claim_end_at = now.checked_add(auction.claim_window_secs).ok_or(OtcError::Overflow)?;
+        consignment.remaining_amount = consignment.remaining_amount.checked_add(left).ok_or(OtcError::Overflow)?;
+        consignment.auction_live = false;
+
+        emit!(AuctionSettled { auction: auction_key, allocated_amount: auction.allocated_amount, returned_amount: left, winners });
+        Ok(())
+    }
+
+    /// Create the approved USDC offer for a winning bid at its revealed discount, the consignment's fixed lockup
+    /// and the clearing price locked at settlement; paid through fulfill_offer_usdc
+    /// Must be claimed before auction.claim_end_at
+    pub fn claim_auction_offer(ctx: Context<ClaimAuctionOffer>) -> Result<()> {
+        let desk_key = ctx.accounts.desk.key();
+        let auction_key = ctx.accounts.auction.key();
+        let bid_key = ctx.accounts.sealed_bid.key();
+        let offer_key = ctx.accounts.offer.key();
+        let bidder_key = ctx.accounts.bidder.key();
+        let now = Clock::get()?.unix_timestamp;
+        let desk = &mut ctx.accounts.desk;
+        require!(!desk.paused, OtcError::Paused);
+        let auction = &ctx.accounts.auction;
+        require!(auction.settled && now <= auction.claim_end_at, OtcError::AuctionPhase);
+
+        let bid = &mut ctx.accounts.sealed_bid;
+        let allocated = auction_allocation(auction, bid.bid_index);
+        require!(allocated > 0 && !bid.offer_created, OtcError::BadState);
+
+        let consignment = &ctx.accounts.consignment;
+        let registry = &ctx.accounts.token_registry;
+        let price_8d = auction.clearing_price_8d;
+
+        let usd_8d = calc_discounted_usd(allocated, price_8d, registry.decimals, bid.discount_bps)?;
+        check_compliance(
+            desk,
+            COMPLIANCE_CREATE_OFFER_FROM_CONSIGNMENT,
+            ctx.accounts.compliance_config.as_deref(),
+            &ctx.accounts.bidder_attestation,
+            usd_8d,
+            now,
+        )?;
+
+        let lockup_secs = (consignment.fixed_lockup_days as i64).checked_mul(86400).ok_or(OtcError::Overflow)?;
+        let commission_bps = desk.p2p_commission_bps;
+        write_consignment_offer(
+            desk,
+            desk_key,
+            consignment,
+            registry,
+            price_8d,
+            &mut ctx.accounts.offer,
+            offer_key,
+            bidder_key,
+            bidder_key,
+            ConsignmentOrder {
+                token_amount: allocated,
+                discount_bps: bid.discount_bps,
+                currency: 1,
+                lockup_secs,
+                agent_commission_bps: commission_bps,
+                referrer: None,
+            },
+            now,
+        )?;
+        let offer = &mut ctx.accounts.offer;
+        offer.approved = true;
+        offer.auction_bid = bid_key; // the deposit stays held until the offer is paid, cancelled or expires
+        bid.allocated_amount = allocated;
+        bid.offer_created = true;
+        bid.offer = offer_key;
+
+        emit!(OfferApproved { offer: offer_key, approver: auction_key });
+        Ok(())
+    }
+
+    /// Settle a bid's deposit after the auction and close the bid, rent to the bidder (anyone can call,
+    /// the desk signs the treasury transfer)
+    /// Losing bids are refunded and unrevealed bids forfeit to the consigner. A winning bid is refunded once its
+    /// offer is paid and forfeits if the offer is cancelled or expires unpaid; an allocation left unclaimed past
+    /// claim_end_at forfeits too and returns to the consignment
+    pub fn release_bid_deposit(ctx: Context<ReleaseBidDeposit>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let auction = &ctx.accounts.auction;
+        require!(auction.settled, OtcError::AuctionPhase);
+        let bid = &ctx.accounts.sealed_bid;
+        let allocated = auction_allocation(auction, bid.bid_index);
+
+        let mut returned_amount = 0;
+        let refund = if !bid.revealed {
+            false
+        } else if allocated == 0 {
+            true
+        } else if bid.offer_created {
+            let offer = ctx.accounts.offer.as_mut().ok_or(OtcError::BadState)?;
+            require!(offer.key() == bid.offer, OtcError::BadState);
+            if !offer.paid {
+                require!(offer.cancelled || now > offer_expiry(&ctx.accounts.desk, offer)?, OtcError::NotExpired);
+            }
+            offer.auction_bid = Pubkey::default();
+            offer.paid
+        } else {
+            require!(now > auction.claim_end_at, OtcError::AuctionPhase);
+            restore_consignment_inventory(&mut ctx.accounts.consignment, allocated)?;
+            returned_amount = allocated;
+            false
+        };
+
+        let to = if refund { bid.bidder } else { ctx.accounts.consignment.consigner };
+        require!(ctx.accounts.recipient_usdc_ata.owner == to, OtcError::BadState);
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.desk_usdc_treasury.to_account_info(),
+            to: ctx.accounts.recipient_usdc_ata.to_account_info(),
+            authority: ctx.accounts.desk_signer.to_account_info(),
+            mint: ctx.accounts.usdc_mint.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        transfer_checked(cpi_ctx, bid.deposit, ctx.accounts.desk.usdc_decimals)?;
+        let desk = &mut ctx.accounts.desk;
+        desk.escrowed_usdc = desk.escrowed_usdc.saturating_sub(bid.deposit);
+
+        emit!(BidDepositReleased {
+            auction: ctx.accounts.auction.key(),
+            bidder: bid.bidder,
+            to,
+            amount: bid.deposit,
+            forfeited: !refund,
+            returned_amount,
+        });
+        Ok(())
+    }
+
+    /// Permissionless crank: delist an expired consignment so nothing more can be sold from it
+    /// This does not return the tokens: the treasury is an ATA of the desk keypair, which the program cannot sign for,
+    /// so the unsold inventory stays there until sweep_expired_consignment runs with the desk signature.
+    /// An unresponsive desk can therefore hold expired inventory; offers already created keep their normal settlement path
+    pub fn expire_consignment(ctx: Context<ExpireConsignment>, _consignment_id: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let consignment_key = ctx.accounts.consignment.key();
+        let consignment = &mut ctx.accounts.consignment;
+        require!(is_consignment_expired(consignment, now), OtcError::NotExpired);
+        require!(consignment.is_active, OtcError::BadState);
+        consignment.is_active = false;
 
+        emit!(ConsignmentExpired { consignment: consignment_key, remaining_amount: consignment.remaining_amount, by: ctx.accounts.caller.key() });
+        Ok(())
+    }
+
+    /// Return the unsold inventory of an expired consignment to the consigner
+    /// Anyone may submit it, but the desk keypair must co-sign the treasury transfer
+    /// The consignment is closed like a withdrawal, so inventory restored later can be swept again
+    pub fn sweep_expired_consignment(ctx: Context<SweepExpiredConsignment>, _consignment_id: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let consignment_key = ctx.accounts.consignment.key();
+        let consignment = &mut ctx.accounts.consignment;
+        require!(is_consignment_expired(consignment, now), OtcError::NotExpired);
+        require!(!consignment.auction_live, OtcError::AuctionPhase);
+        let return_amount = consignment.remaining_amount;
+        require!(return_amount > 0, OtcError::AmountRange);
+
+        consignment.is_active = false;
+        consignment.is_withdrawn = true;
+        consignment.remaining_amount = 0;
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.desk_token_treasury.to_account_info(),
+            to: ctx.accounts.consigner_token_ata.to_account_info(),
+            authority: ctx.accounts.desk_signer.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        transfer_checked(cpi_ctx, return_amount, ctx.accounts.token_mint.decimals)?;
+
+        emit!(ConsignmentSwept { consignment: consignment_key, amount_returned: return_amount });
         Ok(())
     }
 
@@ -221,7 +1012,7 @@ pub mod otc {
         desk.sol_usd_price_8d = sol_usd_8d;
         desk.prices_updated_at = now;
         desk.max_price_age_secs = max_age;
-        emit!(PricesUpdated { token_usd_8d, sol_usd_8d, updated_at: now, max_age });
+        emit!(PricesUpdated { token_usd_8d, sol_usd_8d, updated_at: now, max_age, token_verification: None, sol_verification: None });
         Ok(())
     }
 
@@ -238,6 +1029,35 @@ pub mod otc {
         Ok(())
     }
 
+    /// Confidence limit (conf / price in bps, 0 = none) and price choice for update_token_price_from_pyth (owner only)
+    pub fn set_token_pyth_price_guard(ctx: Context<SetTokenOracleFeed>, max_conf_bps: u16, mode: PythPriceMode) -> Result<()> {
+        require!(max_conf_bps <= 10000, OtcError::AmountRange);
+        let registry_key = ctx.accounts.token_registry.key();
+        let registry = &mut ctx.accounts.token_registry;
+        registry.pyth_max_conf_bps = max_conf_bps;
+        registry.pyth_price_mode = mode;
+        emit!(PythPriceGuardSet { target: registry_key, max_conf_bps, mode });
+        Ok(())
+    }
+
+    /// Confidence limit and price choice for the desk feeds read by update_prices_from_pyth (owner only)
+    pub fn set_pyth_price_guard(ctx: Context<OnlyOwnerDesk>, max_conf_bps: u16, mode: PythPriceMode) -> Result<()> {
+        require!(max_conf_bps <= 10000, OtcError::AmountRange);
+        let desk_key = ctx.accounts.desk.key();
+        let desk = &mut ctx.accounts.desk;
+        desk.pyth_max_conf_bps = max_conf_bps;
+        desk.pyth_price_mode = mode;
+        emit!(PythPriceGuardSet { target: desk_key, max_conf_bps, mode });
+        Ok(())
+    }
+
+    /// Minimum Wormhole verification level a Pyth price update must carry to be used (owner only)
+    pub fn set_pyth_verification_level(ctx: Context<OnlyOwnerDesk>, min_verification: PythVerification) -> Result<()> {
+        ctx.accounts.desk.min_pyth_verification = min_verification;
+        emit!(PythVerificationSet { min_verification });
+        Ok(())
+    }
+
     /// Set/update the pool address and type for automatic price updates
     /// Can be called by owner OR the original registrant (permissionless for the registrant)
     pub fn set_token_pool_config(
@@ -298,14 +1118,21 @@ pub mod otc {
         #[allow(clippy::cast_sign_loss)]
         let max_age = desk.max_price_age_secs as u64;
 
-        let token_price = ctx.accounts.price_feed
-            .get_price_no_older_than(&clock, max_age, &registry.price_feed_id)
-            .map_err(|_| OtcError::StalePrice)?;
-
-        let token_usd_8d = convert_pyth_price(token_price.price, token_price.exponent)?;
+        let token_usd_8d = pyth_price_8d(
+            &ctx.accounts.price_feed,
+            &clock,
+            max_age,
+            &registry.price_feed_id,
+            desk.min_pyth_verification,
+            registry.pyth_max_conf_bps,
+            registry.pyth_price_mode,
+            true,
+        )?;
         check_price_deviation(registry.token_usd_price_8d, token_usd_8d, max_price_deviation_bps)?;
         registry.token_usd_price_8d = token_usd_8d;
         registry.prices_updated_at = current_time;
+        let verification = PythVerification::from_level(ctx.accounts.price_feed.verification_level);
+        emit!(TokenPriceUpdated { token_registry: ctx.accounts.token_registry.key(), token_usd_8d, updated_at: current_time, verification });
         Ok(())
     }
 
@@ -483,18 +1310,10 @@ pub mod otc {
         #[allow(clippy::cast_sign_loss)]
         let max_age = desk.max_price_age_secs as u64;
 
-        // Get prices from Pyth with feed ID validation
-        let token_price = ctx.accounts.token_price_feed
-            .get_price_no_older_than(&clock, max_age, &desk.token_price_feed_id)
-            .map_err(|_| OtcError::StalePrice)?;
-        
-        let sol_price = ctx.accounts.sol_price_feed
-            .get_price_no_older_than(&clock, max_age, &desk.sol_price_feed_id)
-            .map_err(|_| OtcError::StalePrice)?;
-
-        // Convert Pyth prices to our 8-decimal format
-        let token_usd_8d = convert_pyth_price(token_price.price, token_price.exponent)?;
-        let sol_usd_8d = convert_pyth_price(sol_price.price, sol_price.exponent)?;
+        // Get prices from Pyth with feed ID validation, converted to our 8-decimal format
+        let (min_verification, max_conf_bps, mode) = (desk.min_pyth_verification, desk.pyth_max_conf_bps, desk.pyth_price_mode);
+        let token_usd_8d = pyth_price_8d(&ctx.accounts.token_price_feed, &clock, max_age, &desk.token_price_feed_id, min_verification, max_conf_bps, mode, true)?;
+        let sol_usd_8d = pyth_price_8d(&ctx.accounts.sol_price_feed, &clock, max_age, &desk.sol_price_feed_id, min_verification, max_conf_bps, mode, false)?;
 
         // Price deviation check (prevent manipulation/oracle attacks)
         check_price_deviation(desk.token_usd_price_8d, token_usd_8d, max_price_deviation_bps)?;
@@ -508,7 +1327,9 @@ pub mod otc {
             token_usd_8d,
             sol_usd_8d,
             updated_at: current_time,
-            max_age: desk.max_price_age_secs
+            max_age: desk.max_price_age_secs,
+            token_verification: Some(PythVerification::from_level(ctx.accounts.token_price_feed.verification_level)),
+            sol_verification: Some(PythVerification::from_level(ctx.accounts.sol_price_feed.verification_level)),
         });
 
         Ok(())
@@ -565,6 +1386,84 @@ pub mod otc {
         Ok(())
     }
 
+    /// Configure which instructions require a buyer attestation and the per-tier max USD per offer (owner only)
+    /// required_mask: COMPLIANCE_CREATE_OFFER | COMPLIANCE_CREATE_OFFER_FROM_CONSIGNMENT | COMPLIANCE_FULFILL
+    /// tier_max_usd_8d: 0 = no cap for that tier
+    pub fn set_compliance_config(
+        ctx: Context<SetComplianceConfig>,
+        required_mask: u8,
+        tier_max_usd_8d: [u64; COMPLIANCE_TIERS],
+    ) -> Result<()> {
+        require!(required_mask & !COMPLIANCE_ALL == 0, OtcError::BadState);
+        let config = &mut ctx.accounts.compliance_config;
+        config.desk = ctx.accounts.desk.key();
+        config.tier_max_usd_8d = tier_max_usd_8d;
+        ctx.accounts.desk.compliance_required_mask = required_mask;
+        emit!(ComplianceConfigUpdated { required_mask, tier_max_usd_8d });
+        Ok(())
+    }
+
+    /// Set how negotiated consignment offers derive agent commission (owner only)
+    /// Once set, create_offer_from_consignment and create_offer_from_signed_quote must pass the policy account
+    pub fn set_commission_policy(
+        ctx: Context<SetCommissionPolicy>,
+        mode: CommissionMode,
+        flat_bps: u16,
+        min_bps: u16,
+        max_bps: u16,
+        discount_points: Vec<CommissionPoint>,
+        lockup_points: Vec<CommissionPoint>,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.commission_policy;
+        policy.desk = ctx.accounts.desk.key();
+        policy.mode = mode;
+        policy.flat_bps = flat_bps;
+        policy.min_bps = min_bps;
+        policy.max_bps = max_bps;
+        policy.discount_points = discount_points.clone();
+        policy.lockup_points = lockup_points.clone();
+        policy.validate()?;
+        ctx.accounts.desk.commission_policy_enabled = true;
+        emit!(CommissionPolicyUpdated { mode, flat_bps, min_bps, max_bps, discount_points, lockup_points });
+        Ok(())
+    }
+
+    /// Add or remove a compliance issuer allowed to write attestations (owner only)
+    pub fn set_compliance_issuer(ctx: Context<SetComplianceIssuer>, who: Pubkey, allowed: bool) -> Result<()> {
+        let issuers = &mut ctx.accounts.compliance_config.issuers;
+        if allowed {
+            if !issuers.contains(&who) {
+                require!(issuers.len() < MAX_COMPLIANCE_ISSUERS, OtcError::TooManyApprovers);
+                issuers.push(who);
+            }
+        } else if let Some(i) = issuers.iter().position(|x| *x == who) { issuers.remove(i); }
+        Ok(())
+    }
+
+    /// Write a wallet's attestation (owner or compliance issuer)
+    /// denied = true puts the wallet on the denylist regardless of tier/expiry
+    pub fn set_attestation(ctx: Context<SetAttestation>, wallet: Pubkey, tier: u8, expires_at: i64, denied: bool) -> Result<()> {
+        let issuer = ctx.accounts.issuer.key();
+        require!(
+            issuer == ctx.accounts.desk.owner || ctx.accounts.compliance_config.issuers.contains(&issuer),
+            OtcError::NotApprover
+        );
+        require!((tier as usize) < COMPLIANCE_TIERS, OtcError::AmountRange);
+        require!(expires_at >= 0, OtcError::AmountRange);
+
+        let attestation = &mut ctx.accounts.attestation;
+        attestation.desk = ctx.accounts.desk.key();
+        attestation.wallet = wallet;
+        attestation.tier = tier;
+        attestation.expires_at = expires_at;
+        attestation.denied = denied;
+        attestation.issuer = issuer;
+        attestation.updated_at = Clock::get()?.unix_timestamp;
+
+        emit!(AttestationUpdated { wallet, tier, expires_at, denied, issuer });
+        Ok(())
+    }
+
     /// Deposit tokens into desk treasury for a specific registered token
     pub fn deposit_tokens(ctx: Context<DepositTokens>, amount: u64) -> Result<()> {
         require!(amount > 0, OtcError::AmountRange);
@@ -613,8 +1512,17 @@ pub mod otc {
         // Check implied USD value meets minimum
         let total_usd_disc = calc_discounted_usd(token_amount, registry.token_usd_price_8d, registry.decimals, discount_bps)?;
         require!(total_usd_disc >= desk.min_usd_amount_8d, OtcError::MinUsd);
+        check_compliance(
+            desk,
+            COMPLIANCE_CREATE_OFFER,
+            ctx.accounts.compliance_config.as_deref(),
+            &ctx.accounts.beneficiary_attestation,
+            total_usd_disc,
+            now,
+        )?;
 
         require!(lockup_secs >= desk.default_unlock_delay_secs && lockup_secs <= desk.max_lockup_secs, OtcError::AmountRange);
+        check_discount_curve(&desk.discount_curve, discount_bps, lockup_secs)?;
 
         let offer_id = desk.next_offer_id;
         desk.next_offer_id = offer_id.checked_add(1).ok_or(OtcError::Overflow)?;
@@ -642,6 +1550,9 @@ pub mod otc {
         offer.payer = Pubkey::default();
         offer.amount_paid = 0;
         offer.agent_commission_bps = 0; // Direct offers have no agent commission
+        offer.quote_expiry_secs = 0; // Direct offers use desk.quote_expiry_secs
+        offer.rent_payer = ctx.accounts.beneficiary.key();
+        apply_fee_schedule(desk, offer, ctx.accounts.referrer.as_ref().map(|r| r.key()))?;
 
         emit!(OfferCreated {
             desk: offer.desk,
@@ -655,9 +1566,12 @@ pub mod otc {
     }
 
     /// Create an offer from a consignment
-    /// agent_commission_bps: For negotiated deals: 25-150 bps (0.25% - 1.5%)
+    /// discount_bps: For negotiated deals: within the band of the tier token_amount falls in (see set_discount_tiers)
+    ///               For Dutch auction or tiered P2P consignments: the least accepted, the offer gets the assigned discount
+    /// agent_commission_bps: For negotiated deals: 25-150 bps (0.25% - 1.5%), or per the desk
+    ///                       commission policy once set (only honoured in Bounded mode)
     ///                       For P2P (non-negotiable): ignored, uses desk.p2p_commission_bps (default 0.25%)
-    /// Commission is paid to desk.agent from seller proceeds at fulfillment
+    /// Commission is accrued to desk.agent at fulfillment
     pub fn create_offer_from_consignment(
         ctx: Context<CreateOfferFromConsignment>,
         consignment_id: u64,
@@ -666,128 +1580,235 @@ pub mod otc {
         currency: u8,
         lockup_secs: i64,
         agent_commission_bps: u16,
+        allowlist_proof: Vec<[u8; 32]>,
     ) -> Result<()> {
         let desk_key = ctx.accounts.desk.key();
+        let offer_key = ctx.accounts.offer.key();
+        let beneficiary_key = ctx.accounts.beneficiary.key();
         let desk = &mut ctx.accounts.desk;
-        require!(!desk.paused, OtcError::Paused);
-        require!(currency == 0 || currency == 1, OtcError::UnsupportedCurrency);
-
         let consignment = &mut ctx.accounts.consignment;
-        require!(consignment.is_active, OtcError::BadState);
+        require!(consignment.id == consignment_id, OtcError::BadState);
+        check_private_access(desk, consignment, &beneficiary_key, &allowlist_proof)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let total_usd_disc = open_consignment_offer(
+            desk,
+            desk_key,
+            consignment,
+            &ctx.accounts.token_registry,
+            ctx.accounts.commission_policy.as_deref(),
+            &mut ctx.accounts.offer,
+            offer_key,
+            beneficiary_key,
+            beneficiary_key,
+            ConsignmentOrder {
+                token_amount,
+                discount_bps,
+                currency,
+                lockup_secs,
+                agent_commission_bps,
+                referrer: ctx.accounts.referrer.as_ref().map(|r| r.key()),
+            },
+            now,
+        )?;
+        check_compliance(
+            desk,
+            COMPLIANCE_CREATE_OFFER_FROM_CONSIGNMENT,
+            ctx.accounts.compliance_config.as_deref(),
+            &ctx.accounts.beneficiary_attestation,
+            total_usd_disc,
+            now,
+        )?;
+
+        // Non-negotiable offers are auto-approved for P2P (permissionless)
+        // Negotiable offers require agent/approver approval
+        let auto_approved = !consignment.is_negotiable;
+        ctx.accounts.offer.approved = auto_approved;
         
-        // Enforce is_private: only consigner, owner, agent, or approvers can create offers
-        if consignment.is_private {
-            let caller = ctx.accounts.beneficiary.key();
-            require!(
-                caller == consignment.consigner || 
-                caller == desk.owner || 
-                caller == desk.agent || 
-                desk.approvers.contains(&caller),
-                OtcError::FulfillRestricted
-            );
+        // Emit approval event for non-negotiable (P2P) offers
+        if auto_approved {
+            emit!(OfferApproved { offer: offer_key, approver: beneficiary_key });
         }
         
-        require!(token_amount >= consignment.min_deal_amount && token_amount <= consignment.max_deal_amount, OtcError::AmountRange);
-        require!(token_amount <= consignment.remaining_amount, OtcError::InsuffInv);
+        Ok(())
+    }
 
-        // Determine effective commission for the offer
-        let effective_commission_bps: u16 = if consignment.is_negotiable {
-            require!(discount_bps >= consignment.min_discount_bps && discount_bps <= consignment.max_discount_bps, OtcError::Discount);
-            let lockup_days = lockup_secs / 86400;
-            require!(lockup_days >= consignment.min_lockup_days as i64 && lockup_days <= consignment.max_lockup_days as i64, OtcError::LockupTooLong);
-            // Negotiated deals: commission must be 25-150 bps (0.25% - 1.5%)
-            require!(agent_commission_bps >= 25 && agent_commission_bps <= 150, OtcError::CommissionRange);
-            agent_commission_bps
-        } else {
-            require!(discount_bps == consignment.fixed_discount_bps, OtcError::Discount);
-            let lockup_days = lockup_secs / 86400;
-            require!(lockup_days == consignment.fixed_lockup_days as i64, OtcError::LockupTooLong);
-            // P2P deals: use the configured p2p_commission_bps (default 0.25%)
-            // agent_commission_bps parameter is ignored for P2P - uses desk-wide setting
-            desk.p2p_commission_bps
-        };
+    /// Create, approve and pay for a P2P (non-negotiable) consignment offer in one instruction
+    /// Discount and lockup come from the consignment's fixed terms, Dutch auction or discount tier; max_payment bounds the
+    /// USDC (currency 1) or lamports (currency 0) debited from the buyer
+    /// Agent commission is accrued for desk.agent, so no desk signature is needed
+    pub fn buy_from_consignment(
+        ctx: Context<BuyFromConsignment>,
+        consignment_id: u64,
+        token_amount: u64,
+        currency: u8,
+        max_payment: u64,
+        allowlist_proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let desk_key = ctx.accounts.desk.key();
+        let offer_key = ctx.accounts.offer.key();
+        let beneficiary_key = ctx.accounts.beneficiary.key();
+        let desk = &mut ctx.accounts.desk;
+        let consignment = &mut ctx.accounts.consignment;
+        require!(consignment.id == consignment_id, OtcError::BadState);
+        require!(!consignment.is_negotiable, OtcError::NegotiableConsignment);
+        check_private_access(desk, consignment, &beneficiary_key, &allowlist_proof)?;
 
-        // Use registry price for multi-token support
-        let registry = &ctx.accounts.token_registry;
-        require!(registry.token_mint == consignment.token_mint, OtcError::BadState); // Ensure registry matches consignment
-        
-        let price_8d = registry.token_usd_price_8d;
-        require!(price_8d > 0, OtcError::NoPrice);
-        
         let now = Clock::get()?.unix_timestamp;
-        // Check registry price age
-        if registry.prices_updated_at > 0 {
-            require!(now - registry.prices_updated_at <= desk.max_price_age_secs, OtcError::StalePrice);
-        }
+        // Dutch and tiered discounts are assigned by open_consignment_offer; max_payment bounds the price
+        let discount_bps = if has_dynamic_p2p_discount(consignment) { 0 } else { consignment.fixed_discount_bps };
+        let lockup_secs = (consignment.fixed_lockup_days as i64).checked_mul(86400).ok_or(OtcError::Overflow)?;
+        let usd_8d = open_consignment_offer(
+            desk,
+            desk_key,
+            consignment,
+            &ctx.accounts.token_registry,
+            None, // P2P uses desk.p2p_commission_bps
+            &mut ctx.accounts.offer,
+            offer_key,
+            beneficiary_key,
+            beneficiary_key,
+            ConsignmentOrder {
+                token_amount,
+                discount_bps,
+                currency,
+                lockup_secs,
+                agent_commission_bps: 0, // P2P uses desk.p2p_commission_bps
+                referrer: ctx.accounts.referrer.as_ref().map(|r| r.key()),
+            },
+            now,
+        )?;
+        check_compliance(
+            desk,
+            COMPLIANCE_CREATE_OFFER_FROM_CONSIGNMENT | COMPLIANCE_FULFILL,
+            ctx.accounts.compliance_config.as_deref(),
+            &ctx.accounts.beneficiary_attestation,
+            usd_8d,
+            now,
+        )?;
+        require!(ctx.accounts.desk_token_treasury.amount >= token_amount, OtcError::InsuffInv);
 
-        // Check implied USD value meets minimum
-        let total_usd_disc = calc_discounted_usd(token_amount, price_8d, registry.decimals, discount_bps)?;
-        require!(total_usd_disc >= desk.min_usd_amount_8d, OtcError::MinUsd);
+        let offer = &mut ctx.accounts.offer;
+        offer.approved = true;
+        emit!(OfferApproved { offer: offer_key, approver: beneficiary_key });
 
-        consignment.remaining_amount = consignment.remaining_amount.checked_sub(token_amount).ok_or(OtcError::Overflow)?;
-        if consignment.remaining_amount == 0 {
-            consignment.is_active = false;
+        let settlement = settle(&SettlementTerms::for_offer(offer), PaymentCurrency::for_offer(offer, desk)?)?;
+        require!(settlement.payer_debit <= max_payment, OtcError::MaxPaymentExceeded);
+        if currency == 1 {
+            let usdc_mint = ctx.accounts.usdc_mint.as_ref().ok_or(OtcError::BadState)?;
+            let desk_usdc_treasury = ctx.accounts.desk_usdc_treasury.as_ref().ok_or(OtcError::BadState)?;
+            let beneficiary_usdc_ata = ctx.accounts.beneficiary_usdc_ata.as_ref().ok_or(OtcError::BadState)?;
+            let token_program = ctx.accounts.token_program.as_ref().ok_or(OtcError::BadState)?;
+
+            let cpi_accounts = TransferChecked {
+                from: beneficiary_usdc_ata.to_account_info(),
+                to: desk_usdc_treasury.to_account_info(),
+                authority: ctx.accounts.beneficiary.to_account_info(),
+                mint: usdc_mint.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+            transfer_checked(cpi_ctx, settlement.payer_debit, desk.usdc_decimals)?;
+        } else {
+            let ix = anchor_lang::solana_program::system_instruction::transfer(
+                &beneficiary_key,
+                &desk_key,
+                settlement.payer_debit,
+            );
+            anchor_lang::solana_program::program::invoke(&ix, &[
+                ctx.accounts.beneficiary.to_account_info(),
+                desk.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ])?;
         }
 
-        let offer_id = desk.next_offer_id;
-        desk.next_offer_id = offer_id.checked_add(1).ok_or(OtcError::Overflow)?;
+        accrue_settlement_fees(
+            desk,
+            desk_key,
+            offer,
+            offer_key,
+            &settlement,
+            &mut ctx.accounts.agent_commission,
+            ctx.accounts.protocol_fee_accrual.as_deref_mut(),
+            ctx.accounts.referrer_fee_accrual.as_deref_mut(),
+        )?;
 
+        offer.amount_paid = settlement.payer_debit; offer.payer = beneficiary_key; offer.paid = true;
+        emit!(OfferPaid { offer: offer_key, payer: beneficiary_key, amount: settlement.payer_debit, currency });
+        Ok(())
+    }
+
+    /// Create an already-approved offer from a quote negotiated off-chain and signed by desk.agent
+    /// The transaction must carry an Ed25519 sig-verify instruction over the borsh-encoded quote
+    /// immediately before this one; each quote nonce can only be redeemed once per desk
+    pub fn create_offer_from_signed_quote(ctx: Context<CreateOfferFromSignedQuote>, quote: SignedQuote) -> Result<()> {
+        let desk_key = ctx.accounts.desk.key();
         let offer_key = ctx.accounts.offer.key();
         let beneficiary_key = ctx.accounts.beneficiary.key();
-        
-        // Non-negotiable offers are auto-approved for P2P (permissionless)
-        // Negotiable offers require agent/approver approval
-        let auto_approved = !consignment.is_negotiable;
-        
-        let offer = &mut ctx.accounts.offer;
-        
-        offer.desk = desk_key;
-        offer.consignment_id = consignment_id;
-        offer.token_mint = consignment.token_mint;
-        offer.token_decimals = registry.decimals;
-        offer.id = offer_id;
-        offer.beneficiary = beneficiary_key;
-        offer.token_amount = token_amount;
-        offer.discount_bps = discount_bps;
-        offer.created_at = now;
-        offer.unlock_time = now.checked_add(lockup_secs).ok_or(OtcError::Overflow)?;
-        offer.price_usd_per_token_8d = price_8d;
-        offer.max_price_deviation_bps = consignment.max_price_volatility_bps;
-        offer.sol_usd_price_8d = if currency == 0 { desk.sol_usd_price_8d } else { 0 };
-        offer.currency = currency;
-        offer.approved = auto_approved;
-        offer.paid = false;
-        offer.fulfilled = false;
-        offer.cancelled = false;
-        offer.payer = Pubkey::default();
-        offer.amount_paid = 0;
-        offer.agent_commission_bps = effective_commission_bps;
+        let now = Clock::get()?.unix_timestamp;
 
-        emit!(OfferCreated {
-            desk: offer.desk,
-            offer: offer_key,
-            beneficiary: beneficiary_key,
-            token_amount,
-            discount_bps,
-            currency
-        });
-        
-        // Emit approval event for non-negotiable (P2P) offers
-        if auto_approved {
-            emit!(OfferApproved { offer: offer_key, approver: beneficiary_key });
-        }
-        
+        require!(quote.desk == desk_key, OtcError::BadState);
+        require!(quote.consignment == ctx.accounts.consignment.key(), OtcError::BadState);
+        require!(quote.beneficiary == beneficiary_key, OtcError::BadState);
+        require!(now <= quote.expires_at, OtcError::Expired);
+
+        let agent = ctx.accounts.desk.agent;
+        let message = quote.try_to_vec().map_err(|_| OtcError::BadState)?;
+        verify_ed25519_signature(&ctx.accounts.instructions_sysvar.to_account_info(), &agent, &message)?;
+
+        let used_quote = &mut ctx.accounts.used_quote;
+        used_quote.desk = desk_key;
+        used_quote.nonce = quote.nonce;
+        used_quote.offer = offer_key;
+
+        let desk = &mut ctx.accounts.desk;
+        let consignment = &mut ctx.accounts.consignment;
+        let total_usd_disc = open_consignment_offer(
+            desk,
+            desk_key,
+            consignment,
+            &ctx.accounts.token_registry,
+            ctx.accounts.commission_policy.as_deref(),
+            &mut ctx.accounts.offer,
+            offer_key,
+            beneficiary_key,
+            beneficiary_key,
+            ConsignmentOrder {
+                token_amount: quote.token_amount,
+                discount_bps: quote.discount_bps,
+                currency: quote.currency,
+                lockup_secs: quote.lockup_secs,
+                agent_commission_bps: quote.agent_commission_bps,
+                referrer: ctx.accounts.referrer.as_ref().map(|r| r.key()),
+            },
+            now,
+        )?;
+        check_compliance(
+            desk,
+            COMPLIANCE_CREATE_OFFER_FROM_CONSIGNMENT,
+            ctx.accounts.compliance_config.as_deref(),
+            &ctx.accounts.beneficiary_attestation,
+            total_usd_disc,
+            now,
+        )?;
+
+        // The agent's signature is the approval
+        ctx.accounts.offer.approved = true;
+        emit!(OfferApproved { offer: offer_key, approver: agent });
         Ok(())
     }
 
+    /// Withdraw all unsold inventory and close the consignment (consigner only)
+    /// Also sweeps inventory restored by offers cancelled after the withdrawal
     pub fn withdraw_consignment(ctx: Context<WithdrawConsignment>, _consignment_id: u64) -> Result<()> {
         let consignment = &mut ctx.accounts.consignment;
         require!(consignment.consigner == ctx.accounts.consigner.key(), OtcError::NotOwner);
-        require!(consignment.is_active, OtcError::BadState);
+        require!(consignment.is_active || consignment.is_withdrawn, OtcError::BadState);
+        require!(!consignment.auction_live, OtcError::AuctionPhase);
         let withdraw_amount = consignment.remaining_amount;
         require!(withdraw_amount > 0, OtcError::AmountRange);
 
         consignment.is_active = false;
+        consignment.is_withdrawn = true;
         consignment.remaining_amount = 0;
 
         let cpi_accounts = TransferChecked {
@@ -801,6 +1822,41 @@ pub mod otc {
         Ok(())
     }
 
+    /// Withdraw part of the unsold inventory; the consignment stays active while anything remains
+    pub fn partial_withdraw_consignment(ctx: Context<WithdrawConsignment>, _consignment_id: u64, amount: u64) -> Result<()> {
+        let consignment_key = ctx.accounts.consignment.key();
+        let consignment = &mut ctx.accounts.consignment;
+        require!(consignment.consigner == ctx.accounts.consigner.key(), OtcError::NotOwner);
+        require!(consignment.is_active, OtcError::BadState);
+        require!(!consignment.auction_live, OtcError::AuctionPhase);
+        require!(amount > 0, OtcError::AmountRange);
+        require!(amount <= consignment.remaining_amount, OtcError::InsuffInv);
+
+        consignment.remaining_amount = consignment.remaining_amount.checked_sub(amount).ok_or(OtcError::Overflow)?;
+        consignment.total_amount = consignment.total_amount.checked_sub(amount).ok_or(OtcError::Overflow)?;
+        if consignment.remaining_amount == 0 {
+            consignment.is_active = false;
+            consignment.is_withdrawn = true;
+        }
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.desk_token_treasury.to_account_info(),
+            to: ctx.accounts.consigner_token_ata.to_account_info(),
+            authority: ctx.accounts.desk_signer.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        transfer_checked(cpi_ctx, amount, ctx.accounts.token_mint.decimals)?;
+
+        emit!(ConsignmentPartiallyWithdrawn {
+            consignment: consignment_key,
+            amount,
+            total_amount: consignment.total_amount,
+            remaining_amount: consignment.remaining_amount,
+        });
+        Ok(())
+    }
+
     pub fn approve_offer(ctx: Context<ApproveOffer>, _offer_id: u64) -> Result<()> {
         let desk = &ctx.accounts.desk;
         require!(!desk.paused, OtcError::Paused);
@@ -810,15 +1866,119 @@ pub mod otc {
         let approver_key = ctx.accounts.approver.key();
         
         let offer = &mut ctx.accounts.offer;
-        require!(!offer.cancelled && !offer.paid, OtcError::BadState);
-        require!(!offer.approved, OtcError::AlreadyApproved);
-        
-        // Non-negotiable offers are P2P (auto-approved at creation) - cannot be manually approved
-        let consignment = &ctx.accounts.consignment;
-        require!(consignment.is_negotiable, OtcError::NonNegotiableP2P);
-        
-        offer.approved = true;
-        emit!(OfferApproved { offer: offer_key, approver: approver_key });
+        require!(!offer.cancelled && !offer.paid, OtcError::BadState);
+        require!(!offer.approved, OtcError::AlreadyApproved);
+        // A pending counter must be accepted or rejected by the beneficiary first
+        require!(!offer.counter_pending, OtcError::CounterPending);
+        
+        // Non-negotiable offers are P2P (auto-approved at creation) - cannot be manually approved
+        let consignment = &ctx.accounts.consignment;
+        require!(consignment.is_negotiable, OtcError::NonNegotiableP2P);
+        
+        offer.approved = true;
+        emit!(OfferApproved { offer: offer_key, approver: approver_key });
+        Ok(())
+    }
+
+    /// Propose new amount, discount and lockup on an unapproved negotiable offer
+    /// (agent, approver or the consignment's consigner); replaces any pending counter
+    pub fn counter_offer(ctx: Context<CounterOfferTerms>, token_amount: u64, discount_bps: u16, lockup_secs: i64) -> Result<()> {
+        let desk = &ctx.accounts.desk;
+        require!(!desk.paused, OtcError::Paused);
+        let now = Clock::get()?.unix_timestamp;
+        let proposer = ctx.accounts.proposer.key();
+        let consignment = &ctx.accounts.consignment;
+        require!(
+            proposer == desk.agent || desk.approvers.contains(&proposer) || proposer == consignment.consigner,
+            OtcError::NotApprover
+        );
+        require!(consignment.is_negotiable, OtcError::NonNegotiableP2P);
+
+        let offer_key = ctx.accounts.offer.key();
+        let offer = &mut ctx.accounts.offer;
+        require!(!offer.cancelled && !offer.paid, OtcError::BadState);
+        require!(!offer.approved, OtcError::AlreadyApproved);
+        require!(now < offer_expiry(desk, offer)?, OtcError::Expired);
+        check_counter_consignment(consignment, offer, token_amount, now)?;
+
+        // Tiers are checked at the offer's quoted price here and re-checked at the current price on acceptance
+        check_negotiated_band(consignment, token_amount, offer.price_usd_per_token_8d, offer.token_decimals, discount_bps, lockup_secs)?;
+        require!(token_amount >= consignment.min_deal_amount && token_amount <= consignment.max_deal_amount, OtcError::AmountRange);
+        let available = offer.token_amount.checked_add(consignment.remaining_amount).ok_or(OtcError::Overflow)?;
+        require!(token_amount <= available, OtcError::InsuffInv);
+        if !consignment.is_fractionalized {
+            require!(token_amount == available, OtcError::NotFractionalized);
+        }
+
+        offer.counter_round = offer.counter_round.checked_add(1).ok_or(OtcError::Overflow)?;
+        offer.counter_pending = true;
+        offer.counter_token_amount = token_amount;
+        offer.counter_discount_bps = discount_bps;
+        offer.counter_lockup_secs = lockup_secs;
+
+        emit!(OfferCountered { offer: offer_key, by: proposer, round: offer.counter_round, token_amount, discount_bps, lockup_secs });
+        Ok(())
+    }
+
+    /// Accept or reject the pending counter (beneficiary only)
+    /// Accepting re-quotes the offer on the countered terms at the current registry price, ready for approve_offer;
+    /// the offer keeps its original quote expiry
+    pub fn respond_to_counter(ctx: Context<RespondToCounter>, accept: bool) -> Result<()> {
+        let offer_key = ctx.accounts.offer.key();
+        let now = Clock::get()?.unix_timestamp;
+        let desk = &ctx.accounts.desk;
+        require!(!desk.paused, OtcError::Paused);
+
+        let offer = &mut ctx.accounts.offer;
+        require!(offer.counter_pending, OtcError::BadState);
+        require!(!offer.cancelled && !offer.paid && !offer.approved, OtcError::BadState);
+        offer.counter_pending = false;
+
+        if accept {
+            let (token_amount, discount_bps, lockup_secs) = (offer.counter_token_amount, offer.counter_discount_bps, offer.counter_lockup_secs);
+            require!(now < offer_expiry(desk, offer)?, OtcError::Expired);
+            let consignment = &mut ctx.accounts.consignment;
+            require!(consignment.is_negotiable, OtcError::NonNegotiableP2P);
+            check_counter_consignment(consignment, offer, token_amount, now)?;
+            let registry = &ctx.accounts.token_registry;
+            let price_8d = consignment_price_8d(desk, consignment, registry, now)?;
+
+            check_negotiated_band(consignment, token_amount, price_8d, registry.decimals, discount_bps, lockup_secs)?;
+            let commission_bps = negotiated_commission_bps(
+                desk,
+                ctx.accounts.commission_policy.as_deref(),
+                offer.agent_commission_bps,
+                discount_bps,
+                lockup_secs,
+            )?;
+            let total_usd_disc = calc_discounted_usd(token_amount, price_8d, registry.decimals, discount_bps)?;
+            require!(total_usd_disc >= desk.min_usd_amount_8d, OtcError::MinUsd);
+            check_compliance(
+                desk,
+                COMPLIANCE_CREATE_OFFER_FROM_CONSIGNMENT,
+                ctx.accounts.compliance_config.as_deref(),
+                &ctx.accounts.beneficiary_attestation,
+                total_usd_disc,
+                now,
+            )?;
+
+            // Swap the offer's reservation for the countered amount
+            let available = consignment.remaining_amount.checked_add(offer.token_amount).ok_or(OtcError::Overflow)?;
+            consignment.remaining_amount = available.checked_sub(token_amount).ok_or(OtcError::InsuffInv)?;
+            consignment.is_active = consignment.remaining_amount > 0;
+
+            check_reserve_price(consignment.min_net_price_usd_8d, price_8d, discount_bps)?;
+            offer.token_amount = token_amount;
+            offer.discount_bps = discount_bps;
+            offer.agent_commission_bps = commission_bps;
+            offer.min_net_price_usd_8d = consignment.min_net_price_usd_8d;
+            offer.price_usd_per_token_8d = price_8d;
+            offer.sol_usd_price_8d = if offer.currency == 0 { desk.sol_usd_price_8d } else { 0 };
+            // created_at is kept so a counter cannot extend the offer past its quote expiry
+            offer.unlock_time = now.checked_add(lockup_secs).ok_or(OtcError::Overflow)?;
+        }
+
+        emit!(OfferCounterResponse { offer: offer_key, round: offer.counter_round, accepted: accept });
         Ok(())
     }
 
@@ -834,7 +1994,7 @@ pub mod otc {
         require!(!offer.paid && !offer.fulfilled, OtcError::BadState);
         
         if caller == offer.beneficiary {
-            let expiry = offer.created_at.checked_add(desk.quote_expiry_secs).ok_or(OtcError::Overflow)?;
+            let expiry = offer_expiry(desk, offer)?;
             require!(now >= expiry, OtcError::NotExpired);
         } else if caller == desk.owner || caller == desk.agent || desk.approvers.contains(&caller) {
         } else {
@@ -865,7 +2025,7 @@ pub mod otc {
         require!(offer.consignment_id > 0, OtcError::BadState); // Must be from consignment
         
         if caller == offer.beneficiary {
-            let expiry = offer.created_at.checked_add(desk.quote_expiry_secs).ok_or(OtcError::Overflow)?;
+            let expiry = offer_expiry(desk, offer)?;
             require!(now >= expiry, OtcError::NotExpired);
         } else if caller == desk.owner || caller == desk.agent || desk.approvers.contains(&caller) {
         } else {
@@ -876,42 +2036,73 @@ pub mod otc {
         offer.cancelled = true;
         
         // Restore tokens to consignment
-        let consignment = &mut ctx.accounts.consignment;
-        consignment.remaining_amount = consignment.remaining_amount.checked_add(token_amount).ok_or(OtcError::Overflow)?;
-        if !consignment.is_active {
-            consignment.is_active = true;
-        }
+        restore_consignment_inventory(&mut ctx.accounts.consignment, token_amount)?;
         
         emit!(OfferCancelled { offer: offer_key, by: caller });
         Ok(())
     }
 
+    /// Permissionless crank: cancel an expired unpaid offer (restoring consignment inventory)
+    /// and close any cancelled, refunded or claimed offer, returning its rent to the rent payer
+    pub fn cleanup_offer(ctx: Context<CleanupOffer>) -> Result<()> {
+        let desk = &ctx.accounts.desk;
+        let caller = ctx.accounts.caller.key();
+        let offer_key = ctx.accounts.offer.key();
+        let now = Clock::get()?.unix_timestamp;
+
+        let offer = &mut ctx.accounts.offer;
+        require!(offer.auction_bid == Pubkey::default(), OtcError::DepositPending);
+        if !offer.paid && !offer.cancelled {
+            let expiry = offer_expiry(desk, offer)?;
+            require!(now > expiry, OtcError::NotExpired);
+            offer.cancelled = true;
+
+            // Restore tokens to consignment
+            if offer.consignment_id > 0 {
+                let consignment = ctx.accounts.consignment.as_mut().ok_or(OtcError::BadState)?;
+                restore_consignment_inventory(consignment, offer.token_amount)?;
+            }
+            emit!(OfferCancelled { offer: offer_key, by: caller });
+        }
+        require!(offer.cancelled || offer.fulfilled, OtcError::BadState);
+
+        let rent_recipient = ctx.accounts.rent_recipient.to_account_info();
+        emit!(OfferClosed { offer: offer_key, rent_recipient: rent_recipient.key(), by: caller });
+        offer.close(rent_recipient)
+    }
+
     pub fn fulfill_offer_usdc(ctx: Context<FulfillOfferUsdc>, _offer_id: u64) -> Result<()> {
         // Cache keys before mutable borrows to avoid borrow checker issues
         let offer_key = ctx.accounts.offer.key();
         let payer_key = ctx.accounts.payer.key();
+        let desk_key = ctx.accounts.desk.key();
         
         let desk = &mut ctx.accounts.desk;
-        require!(!desk.paused, OtcError::Paused);
         // Removed PDA validation - now using keypairs for offers
         let offer = &mut ctx.accounts.offer;
         require!(offer.currency == 1, OtcError::BadState);
-        require!(offer.approved, OtcError::NotApproved);
-        require!(!offer.cancelled && !offer.paid && !offer.fulfilled, OtcError::BadState);
         let now = Clock::get()?.unix_timestamp;
-        let expiry = offer.created_at.checked_add(desk.quote_expiry_secs).ok_or(OtcError::Overflow)?;
-        require!(now <= expiry, OtcError::Expired);
-        require!(ctx.accounts.desk_token_treasury.amount >= offer.token_amount, OtcError::InsuffInv);
-        if desk.restrict_fulfill {
-            let caller = ctx.accounts.payer.key();
-            require!(caller == offer.beneficiary || caller == desk.owner || caller == desk.agent || desk.approvers.contains(&caller), OtcError::FulfillRestricted);
+        let reserve_price_8d = reserve_check_price_8d(desk, offer, ctx.accounts.consignment.as_deref(), ctx.accounts.token_registry.as_deref(), now)?;
+        check_fulfillable(desk, offer, &payer_key, ctx.accounts.desk_token_treasury.amount, reserve_price_8d, now)?;
+        let settlement = settle(&SettlementTerms::for_offer(offer), PaymentCurrency::Usdc)?;
+        check_compliance(
+            desk,
+            COMPLIANCE_FULFILL,
+            ctx.accounts.compliance_config.as_deref(),
+            &ctx.accounts.beneficiary_attestation,
+            settlement.discounted_usd_8d,
+            now,
+        )?;
+        if payer_key != offer.beneficiary {
+            check_compliance(
+                desk,
+                COMPLIANCE_FULFILL,
+                ctx.accounts.compliance_config.as_deref(),
+                &ctx.accounts.payer_attestation,
+                settlement.discounted_usd_8d,
+                now,
+            )?;
         }
-        let usd_8d = calc_discounted_usd(offer.token_amount, offer.price_usd_per_token_8d, offer.token_decimals, offer.discount_bps)?;
-        let usdc_amount = safe_u128_to_u64(mul_div_ceil_u128(usd_8d as u128, 1_000_000u128, 100_000_000u128)?)?;
-        
-        // Calculate agent commission (from seller proceeds)
-        let commission_usd_8d = usd_8d.checked_mul(offer.agent_commission_bps as u64).ok_or(OtcError::Overflow)?.checked_div(10_000).ok_or(OtcError::Overflow)?;
-        let commission_usdc = safe_u128_to_u64(mul_div_u128(commission_usd_8d as u128, 1_000_000u128, 100_000_000u128)?)?;
         
         // Transfer full payment from buyer to desk treasury
         let cpi_accounts = TransferChecked { 
@@ -921,29 +2112,23 @@ pub mod otc {
             mint: ctx.accounts.usdc_mint.to_account_info(),
         };
         let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
-        transfer_checked(cpi_ctx, usdc_amount, desk.usdc_decimals)?;
-        
-        // If there's a commission and agent USDC account is provided, transfer commission to agent
-        // SECURITY: Validate agent_usdc_ata owner matches desk.agent to prevent commission theft
-        if commission_usdc > 0 {
-            if let Some(agent_usdc_ata) = &ctx.accounts.agent_usdc_ata {
-                require!(agent_usdc_ata.owner == desk.agent, OtcError::BadState);
-                // Transfer commission from desk treasury to agent (desk_signer authorizes)
-                let cpi_accounts_commission = TransferChecked { 
-                    from: ctx.accounts.desk_usdc_treasury.to_account_info(), 
-                    to: agent_usdc_ata.to_account_info(), 
-                    authority: ctx.accounts.desk_signer.to_account_info(),
-                    mint: ctx.accounts.usdc_mint.to_account_info(),
-                };
-                let cpi_ctx_commission = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts_commission);
-                transfer_checked(cpi_ctx_commission, commission_usdc, desk.usdc_decimals)?;
-                emit!(AgentCommissionPaid { offer: offer_key, agent: desk.agent, amount: commission_usdc, currency: 1 });
-            }
-        }
+        transfer_checked(cpi_ctx, settlement.payer_debit, desk.usdc_decimals)?;
         
-        offer.amount_paid = usdc_amount; offer.payer = payer_key; offer.paid = true;
+        // Fees stay in the treasury and are credited to each recipient's accrual account
+        accrue_settlement_fees(
+            desk,
+            desk_key,
+            offer,
+            offer_key,
+            &settlement,
+            &mut ctx.accounts.agent_commission,
+            ctx.accounts.protocol_fee_accrual.as_deref_mut(),
+            ctx.accounts.referrer_fee_accrual.as_deref_mut(),
+        )?;
+
+        offer.amount_paid = settlement.payer_debit; offer.payer = payer_key; offer.paid = true;
         // Note: desk.token_reserved is deprecated since all tokens are equal now
-        emit!(OfferPaid { offer: offer_key, payer: payer_key, amount: usdc_amount, currency: 1 });
+        emit!(OfferPaid { offer: offer_key, payer: payer_key, amount: settlement.payer_debit, currency: 1 });
         Ok(())
     }
 
@@ -955,53 +2140,55 @@ pub mod otc {
         let desk_ai = ctx.accounts.desk.to_account_info();
         let desk_key = desk_ai.key();
         let desk = &mut ctx.accounts.desk;
-        let agent_key = desk.agent;
-        require!(!desk.paused, OtcError::Paused);
         // Removed PDA validation - now using keypairs for offers
         let offer = &mut ctx.accounts.offer;
         require!(offer.currency == 0, OtcError::BadState);
-        require!(offer.approved, OtcError::NotApproved);
-        require!(!offer.cancelled && !offer.paid && !offer.fulfilled, OtcError::BadState);
         let now = Clock::get()?.unix_timestamp;
-        let expiry = offer.created_at.checked_add(desk.quote_expiry_secs).ok_or(OtcError::Overflow)?;
-        require!(now <= expiry, OtcError::Expired);
-        require!(ctx.accounts.desk_token_treasury.amount >= offer.token_amount, OtcError::InsuffInv);
-        if desk.restrict_fulfill {
-            let caller = ctx.accounts.payer.key();
-            require!(caller == offer.beneficiary || caller == desk.owner || caller == desk.agent || desk.approvers.contains(&caller), OtcError::FulfillRestricted);
+        let reserve_price_8d = reserve_check_price_8d(desk, offer, ctx.accounts.consignment.as_deref(), ctx.accounts.token_registry.as_deref(), now)?;
+        check_fulfillable(desk, offer, &payer_key, ctx.accounts.desk_token_treasury.amount, reserve_price_8d, now)?;
+        let settlement = settle(&SettlementTerms::for_offer(offer), PaymentCurrency::for_offer(offer, desk)?)?;
+        check_compliance(
+            desk,
+            COMPLIANCE_FULFILL,
+            ctx.accounts.compliance_config.as_deref(),
+            &ctx.accounts.beneficiary_attestation,
+            settlement.discounted_usd_8d,
+            now,
+        )?;
+        if payer_key != offer.beneficiary {
+            check_compliance(
+                desk,
+                COMPLIANCE_FULFILL,
+                ctx.accounts.compliance_config.as_deref(),
+                &ctx.accounts.payer_attestation,
+                settlement.discounted_usd_8d,
+                now,
+            )?;
         }
-        let usd_8d = calc_discounted_usd(offer.token_amount, offer.price_usd_per_token_8d, offer.token_decimals, offer.discount_bps)?;
-        let sol_usd = if offer.sol_usd_price_8d > 0 { offer.sol_usd_price_8d } else { desk.sol_usd_price_8d };
-        require!(sol_usd > 0, OtcError::NoPrice);
-        let lamports_req = safe_u128_to_u64(mul_div_ceil_u128(usd_8d as u128, 1_000_000_000u128, sol_usd as u128)?)?;
-        
-        // Calculate agent commission (from seller proceeds)
-        let commission_usd_8d = usd_8d.checked_mul(offer.agent_commission_bps as u64).ok_or(OtcError::Overflow)?.checked_div(10_000).ok_or(OtcError::Overflow)?;
-        let commission_lamports = safe_u128_to_u64(mul_div_u128(commission_usd_8d as u128, 1_000_000_000u128, sol_usd as u128)?)?;
         
         // Transfer full payment from buyer to desk
-        let ix = anchor_lang::solana_program::system_instruction::transfer(&ctx.accounts.payer.key(), &desk_key, lamports_req);
+        let ix = anchor_lang::solana_program::system_instruction::transfer(&payer_key, &desk_key, settlement.payer_debit);
         anchor_lang::solana_program::program::invoke(&ix, &[
             ctx.accounts.payer.to_account_info(),
             desk_ai.clone(),
             ctx.accounts.system_program.to_account_info(),
         ])?;
         
-        // If there's a commission and agent account is provided, transfer commission to agent
-        // SECURITY: Validate agent account matches desk.agent to prevent commission theft
-        if commission_lamports > 0 {
-            if let Some(agent_account) = &ctx.accounts.agent {
-                require!(agent_account.key() == agent_key, OtcError::BadState);
-                // Transfer commission from desk to agent (desk_signer authorizes)
-                **desk_ai.try_borrow_mut_lamports()? -= commission_lamports;
-                **agent_account.to_account_info().try_borrow_mut_lamports()? += commission_lamports;
-                emit!(AgentCommissionPaid { offer: offer_key, agent: agent_key, amount: commission_lamports, currency: 0 });
-            }
-        }
-        
-        offer.amount_paid = lamports_req; offer.payer = payer_key; offer.paid = true;
+        // Fees stay with the desk and are credited to each recipient's accrual account
+        accrue_settlement_fees(
+            desk,
+            desk_key,
+            offer,
+            offer_key,
+            &settlement,
+            &mut ctx.accounts.agent_commission,
+            ctx.accounts.protocol_fee_accrual.as_deref_mut(),
+            ctx.accounts.referrer_fee_accrual.as_deref_mut(),
+        )?;
+
+        offer.amount_paid = settlement.payer_debit; offer.payer = payer_key; offer.paid = true;
         // Note: desk.token_reserved is deprecated since all tokens are equal now
-        emit!(OfferPaid { offer: offer_key, payer: payer_key, amount: lamports_req, currency: 0 });
+        emit!(OfferPaid { offer: offer_key, payer: payer_key, amount: settlement.payer_debit, currency: 0 });
         Ok(())
     }
 
@@ -1034,6 +2221,340 @@ pub mod otc {
         Ok(())
     }
 
+    /// Deliver every matured offer in a batch of one token mint
+    /// remaining_accounts: (offer, beneficiary_token_ata) pairs, all writable
+    /// Offers that are locked, already claimed, unpaid or mismatched are skipped and reported, not failed
+    pub fn batch_claim<'info>(ctx: Context<'_, '_, 'info, 'info, BatchClaim<'info>>) -> Result<()> {
+        // Desk keypair signs to authorize token transfers
+        let desk = &ctx.accounts.desk;
+        require!(!desk.paused, OtcError::Paused);
+        let pairs = ctx.remaining_accounts.chunks_exact(2);
+        require!(!ctx.remaining_accounts.is_empty() && pairs.remainder().is_empty(), OtcError::AmountRange);
+
+        let desk_key = desk.key();
+        let token_mint = ctx.accounts.token_mint.key();
+        let now = Clock::get()?.unix_timestamp;
+
+        for pair in pairs {
+            let (offer_info, ata_info) = (&pair[0], &pair[1]);
+            require!(offer_info.is_writable && ata_info.is_writable, OtcError::BadState);
+            let mut offer: Account<'info, Offer> = Account::try_from(offer_info)?;
+            require!(offer.desk == desk_key, OtcError::BadState);
+
+            let ata_matches = InterfaceAccount::<TokenAccount>::try_from(ata_info)
+                .map(|ata| ata.mint == offer.token_mint && ata.owner == offer.beneficiary)
+                .unwrap_or(false);
+            let status = if offer.fulfilled {
+                ClaimStatus::AlreadyClaimed
+            } else if !offer.paid || offer.cancelled {
+                ClaimStatus::NotClaimable
+            } else if offer.token_mint != token_mint || !ata_matches {
+                ClaimStatus::InvalidAccounts
+            } else if now < offer.unlock_time {
+                ClaimStatus::Locked
+            } else {
+                ClaimStatus::Claimed
+            };
+
+            let amount = if status == ClaimStatus::Claimed { offer.token_amount } else { 0 };
+            if status == ClaimStatus::Claimed {
+                let cpi_accounts = TransferChecked {
+                    from: ctx.accounts.desk_token_treasury.to_account_info(),
+                    to: ata_info.clone(),
+                    authority: ctx.accounts.desk_signer.to_account_info(),
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+                transfer_checked(cpi_ctx, offer.token_amount, offer.token_decimals)?;
+                offer.fulfilled = true;
+                offer.exit(&crate::ID)?;
+                emit!(TokensClaimed { offer: offer_info.key(), beneficiary: offer.beneficiary, amount });
+            }
+            emit!(BatchClaimResult { offer: offer_info.key(), beneficiary: offer.beneficiary, amount, status });
+        }
+        Ok(())
+    }
+
+    /// Open a fee accrual account for a protocol treasury or referrer (permissionless, caller pays rent)
+    /// Agent accruals are opened automatically at fulfillment
+    pub fn open_fee_account(ctx: Context<OpenFeeAccount>, recipient: Pubkey, currency: u8) -> Result<()> {
+        require!(currency == 0 || currency == 1, OtcError::UnsupportedCurrency);
+        let accrual = &mut ctx.accounts.fee_accrual;
+        accrual.desk = ctx.accounts.desk.key();
+        accrual.recipient = recipient;
+        accrual.currency = currency;
+        Ok(())
+    }
+
+    /// Pay out fees accrued to the signing recipient in one currency (0 = SOL, 1 = USDC)
+    pub fn claim_commission(ctx: Context<ClaimCommission>, currency: u8) -> Result<()> {
+        let recipient_key = ctx.accounts.recipient.key();
+        let desk = &mut ctx.accounts.desk;
+        let accrual = &mut ctx.accounts.fee_accrual;
+        let amount = accrual.accrued_amount.checked_sub(accrual.claimed_amount).ok_or(OtcError::Overflow)?;
+        require!(amount > 0, OtcError::NothingToClaim);
+
+        if currency == 1 {
+            let usdc_mint = ctx.accounts.usdc_mint.as_ref().ok_or(OtcError::BadState)?;
+            let desk_usdc_treasury = ctx.accounts.desk_usdc_treasury.as_ref().ok_or(OtcError::BadState)?;
+            let recipient_usdc_ata = ctx.accounts.recipient_usdc_ata.as_ref().ok_or(OtcError::BadState)?;
+            let token_program = ctx.accounts.token_program.as_ref().ok_or(OtcError::BadState)?;
+            let cpi_accounts = TransferChecked {
+                from: desk_usdc_treasury.to_account_info(),
+                to: recipient_usdc_ata.to_account_info(),
+                authority: ctx.accounts.desk_signer.to_account_info(),
+                mint: usdc_mint.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+            transfer_checked(cpi_ctx, amount, desk.usdc_decimals)?;
+            desk.commission_owed_usdc = desk.commission_owed_usdc.saturating_sub(amount);
+        } else {
+            **desk.to_account_info().try_borrow_mut_lamports()? -= amount;
+            **ctx.accounts.recipient.to_account_info().try_borrow_mut_lamports()? += amount;
+            desk.commission_owed_lamports = desk.commission_owed_lamports.saturating_sub(amount);
+        }
+
+        accrual.claimed_amount = accrual.accrued_amount;
+        emit!(FeeClaimed { recipient: recipient_key, amount, currency });
+        Ok(())
+    }
+
+    /// View: settlement breakdown for an existing offer, returned via return data (call through simulation)
+    pub fn quote_offer(ctx: Context<QuoteOffer>) -> Result<SettlementQuote> {
+        let desk = &ctx.accounts.desk;
+        let offer = &ctx.accounts.offer;
+        let settlement = settle(&SettlementTerms::for_offer(offer), PaymentCurrency::for_offer(offer, desk)?)?;
+        Ok(SettlementQuote::new(&settlement, offer.unlock_time))
+    }
+
+    /// View: settlement breakdown for an order against a consignment, priced and validated like
+    /// create_offer_from_consignment / buy_from_consignment would at the current price (referrer None = no referrer share)
+    pub fn quote_order(
+        ctx: Context<QuoteOrder>,
+        consignment_id: u64,
+        token_amount: u64,
+        discount_bps: u16,
+        currency: u8,
+        lockup_secs: i64,
+        agent_commission_bps: u16,
+        referrer: Option<Pubkey>,
+    ) -> Result<SettlementQuote> {
+        let desk = &ctx.accounts.desk;
+        let consignment = &ctx.accounts.consignment;
+        let registry = &ctx.accounts.token_registry;
+        require!(consignment.id == consignment_id, OtcError::BadState);
+        let now = Clock::get()?.unix_timestamp;
+        let order = ConsignmentOrder { token_amount, discount_bps, currency, lockup_secs, agent_commission_bps, referrer };
+        let priced = price_consignment_order(desk, consignment, registry, ctx.accounts.commission_policy.as_deref(), &order, now)?;
+        check_reserve_price(consignment.min_net_price_usd_8d, priced.price_8d, priced.discount_bps)?;
+        let terms = SettlementTerms {
+            token_amount,
+            price_usd_per_token_8d: priced.price_8d,
+            token_decimals: registry.decimals,
+            discount_bps: priced.discount_bps,
+            commission_bps: priced.commission_bps,
+            protocol_fee_bps: desk.protocol_fee_bps,
+            referrer_bps: if referrer.is_some() { desk.referrer_bps } else { 0 },
+        };
+        let settlement = settle(&terms, PaymentCurrency::from_code(currency, desk.sol_usd_price_8d)?)?;
+        Ok(SettlementQuote::new(&settlement, now.checked_add(lockup_secs).ok_or(OtcError::Overflow)?))
+    }
+
+    /// Post a buy-side RFQ: buy up to max_token_amount of a registered token at discount_bps with
+    /// lockup_secs, escrowing escrow_amount USDC in the desk treasury (expires_at 0 = no expiry)
+    pub fn create_bid(
+        ctx: Context<CreateBid>,
+        max_token_amount: u64,
+        discount_bps: u16,
+        lockup_secs: i64,
+        escrow_amount: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        let desk = &ctx.accounts.desk;
+        require!(!desk.paused, OtcError::Paused);
+        require!(ctx.accounts.token_registry.is_active, OtcError::BadState);
+        require!(max_token_amount > 0 && escrow_amount > 0, OtcError::AmountRange);
+        require!(discount_bps <= 10000, OtcError::Discount);
+        require!(lockup_secs >= desk.default_unlock_delay_secs && lockup_secs <= desk.max_lockup_secs, OtcError::AmountRange);
+        let now = Clock::get()?.unix_timestamp;
+        require!(expires_at == 0 || expires_at > now, OtcError::AmountRange);
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.buyer_usdc_ata.to_account_info(),
+            to: ctx.accounts.desk_usdc_treasury.to_account_info(),
+            authority: ctx.accounts.buyer.to_account_info(),
+            mint: ctx.accounts.usdc_mint.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        transfer_checked(cpi_ctx, escrow_amount, desk.usdc_decimals)?;
+
+        let desk_key = ctx.accounts.desk.key();
+        let bid_key = ctx.accounts.bid.key();
+        let buyer_key = ctx.accounts.buyer.key();
+        let token_mint = ctx.accounts.token_registry.token_mint;
+        let desk = &mut ctx.accounts.desk;
+        desk.escrowed_usdc = desk.escrowed_usdc.checked_add(escrow_amount).ok_or(OtcError::Overflow)?;
+
+        let bid = &mut ctx.accounts.bid;
+        bid.desk = desk_key;
+        bid.buyer = buyer_key;
+        bid.token_mint = token_mint;
+        bid.max_token_amount = max_token_amount;
+        bid.filled_amount = 0;
+        bid.discount_bps = discount_bps;
+        bid.lockup_secs = lockup_secs;
+        bid.escrow_remaining = escrow_amount;
+        bid.created_at = now;
+        bid.expires_at = expires_at;
+
+        emit!(BidCreated { bid: bid_key, buyer: buyer_key, token_mint, max_token_amount, discount_bps, lockup_secs, escrow_amount, expires_at });
+        Ok(())
+    }
+
+    /// Sell into a bid, fully or partly, from the seller's wallet or from a consignment they own
+    /// Seller and desk sign. Wallet fills need the desk too: the seller is paid out of the bid's escrow, which sits in
+    /// the desk keypair's USDC treasury, so a holder cannot fill a bid without the desk co-signing
+    /// Creates a paid USDC offer for the buyer on the bid's terms, pays the seller's net from escrow and accrues
+    /// fees like a fulfillment; consignment fills are priced and sized like any other sale from the consignment
+    /// The seller is paid up front, so the offer cannot be emergency refunded
+    pub fn fill_bid(ctx: Context<FillBid>, token_amount: u64) -> Result<()> {
+        let desk_key = ctx.accounts.desk.key();
+        let bid_key = ctx.accounts.bid.key();
+        let offer_key = ctx.accounts.offer.key();
+        let seller_key = ctx.accounts.seller.key();
+        let now = Clock::get()?.unix_timestamp;
+        require!(!ctx.accounts.desk.paused, OtcError::Paused);
+
+        let bid = &mut ctx.accounts.bid;
+        require!(bid.expires_at == 0 || now < bid.expires_at, OtcError::Expired);
+        require!(token_amount > 0 && token_amount <= bid.max_token_amount - bid.filled_amount, OtcError::AmountRange);
+        let registry = &ctx.accounts.token_registry;
+        require!(registry.is_active, OtcError::BadState);
+
+        // Inventory comes from the seller's consignment or is deposited from their wallet
+        let (consignment_id, price_8d, min_net_price_usd_8d) = match ctx.accounts.consignment.as_deref_mut() {
+            Some(consignment) => {
+                require!(consignment.consigner == seller_key, OtcError::NotOwner);
+                check_consignment_sale(consignment, 1, token_amount, now)?;
+                let price_8d = consignment_price_8d(&ctx.accounts.desk, consignment, registry, now)?;
+                check_reserve_price(consignment.min_net_price_usd_8d, price_8d, bid.discount_bps)?;
+                consignment.remaining_amount -= token_amount;
+                if consignment.remaining_amount == 0 {
+                    consignment.is_active = false;
+                }
+                (consignment.id, price_8d, consignment.min_net_price_usd_8d)
+            }
+            None => {
+                require!(registry.token_usd_price_8d > 0, OtcError::NoPrice);
+                if registry.prices_updated_at > 0 {
+                    require!(now - registry.prices_updated_at <= ctx.accounts.desk.max_price_age_secs, OtcError::StalePrice);
+                }
+                let seller_token_ata = ctx.accounts.seller_token_ata.as_ref().ok_or(OtcError::BadState)?;
+                let cpi_accounts = TransferChecked {
+                    from: seller_token_ata.to_account_info(),
+                    to: ctx.accounts.desk_token_treasury.to_account_info(),
+                    authority: ctx.accounts.seller.to_account_info(),
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+                transfer_checked(cpi_ctx, token_amount, registry.decimals)?;
+                (0, registry.token_usd_price_8d, 0)
+            }
+        };
+
+        let desk = &mut ctx.accounts.desk;
+        let offer_id = desk.next_offer_id;
+        desk.next_offer_id = offer_id.checked_add(1).ok_or(OtcError::Overflow)?;
+
+        let offer = &mut ctx.accounts.offer;
+        offer.desk = desk_key;
+        offer.consignment_id = consignment_id;
+        offer.token_mint = registry.token_mint;
+        offer.token_decimals = registry.decimals;
+        offer.id = offer_id;
+        offer.beneficiary = bid.buyer;
+        offer.token_amount = token_amount;
+        offer.discount_bps = bid.discount_bps;
+        offer.created_at = now;
+        offer.unlock_time = now.checked_add(bid.lockup_secs).ok_or(OtcError::Overflow)?;
+        offer.price_usd_per_token_8d = price_8d;
+        offer.max_price_deviation_bps = 0;
+        offer.sol_usd_price_8d = 0;
+        offer.currency = 1;
+        offer.approved = true;
+        offer.fulfilled = false;
+        offer.cancelled = false;
+        offer.agent_commission_bps = desk.p2p_commission_bps;
+        offer.quote_expiry_secs = 0;
+        offer.rent_payer = seller_key;
+        offer.min_net_price_usd_8d = min_net_price_usd_8d;
+        offer.seller_paid = true;
+        apply_fee_schedule(desk, offer, None)?;
+
+        let settlement = settle(&SettlementTerms::for_offer(offer), PaymentCurrency::Usdc)?;
+        require!(settlement.discounted_usd_8d >= desk.min_usd_amount_8d, OtcError::MinUsd);
+        require!(settlement.payer_debit <= bid.escrow_remaining, OtcError::MaxPaymentExceeded);
+        let create_flag = if consignment_id > 0 { COMPLIANCE_CREATE_OFFER_FROM_CONSIGNMENT } else { COMPLIANCE_CREATE_OFFER };
+        check_compliance(
+            desk,
+            create_flag | COMPLIANCE_FULFILL,
+            ctx.accounts.compliance_config.as_deref(),
+            &ctx.accounts.buyer_attestation,
+            settlement.discounted_usd_8d,
+            now,
+        )?;
+        bid.escrow_remaining -= settlement.payer_debit;
+        bid.filled_amount += token_amount;
+        desk.escrowed_usdc = desk.escrowed_usdc.saturating_sub(settlement.payer_debit);
+        offer.payer = bid.buyer;
+        offer.amount_paid = settlement.payer_debit;
+        offer.paid = true;
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.desk_usdc_treasury.to_account_info(),
+            to: ctx.accounts.seller_usdc_ata.to_account_info(),
+            authority: ctx.accounts.desk_signer.to_account_info(),
+            mint: ctx.accounts.usdc_mint.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        transfer_checked(cpi_ctx, settlement.seller_net, desk.usdc_decimals)?;
+        accrue_settlement_fees(
+            desk,
+            desk_key,
+            offer,
+            offer_key,
+            &settlement,
+            &mut ctx.accounts.agent_commission,
+            ctx.accounts.protocol_fee_accrual.as_deref_mut(),
+            None,
+        )?;
+
+        emit!(OfferCreated { desk: desk_key, offer: offer_key, beneficiary: offer.beneficiary, token_amount, discount_bps: offer.discount_bps, currency: 1 });
+        emit!(OfferApproved { offer: offer_key, approver: bid_key });
+        emit!(OfferPaid { offer: offer_key, payer: offer.payer, amount: settlement.payer_debit, currency: 1 });
+        emit!(BidFilled { bid: bid_key, offer: offer_key, seller: seller_key, token_amount, payment: settlement.payer_debit, seller_net: settlement.seller_net });
+        Ok(())
+    }
+
+    /// Cancel a bid and refund its unspent escrow to the buyer (buyer only)
+    pub fn cancel_bid(ctx: Context<CancelBid>) -> Result<()> {
+        let refunded = ctx.accounts.bid.escrow_remaining;
+        if refunded > 0 {
+            let cpi_accounts = TransferChecked {
+                from: ctx.accounts.desk_usdc_treasury.to_account_info(),
+                to: ctx.accounts.buyer_usdc_ata.to_account_info(),
+                authority: ctx.accounts.desk_signer.to_account_info(),
+                mint: ctx.accounts.usdc_mint.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+            transfer_checked(cpi_ctx, refunded, ctx.accounts.desk.usdc_decimals)?;
+            let desk = &mut ctx.accounts.desk;
+            desk.escrowed_usdc = desk.escrowed_usdc.saturating_sub(refunded);
+        }
+        emit!(BidCancelled { bid: ctx.accounts.bid.key(), refunded });
+        Ok(())
+    }
+
     /// Withdraw tokens from desk treasury for any registered token
     pub fn withdraw_tokens(ctx: Context<WithdrawTokens>, amount: u64) -> Result<()> {
         // Desk keypair signs to authorize withdrawal
@@ -1056,6 +2577,10 @@ pub mod otc {
         // Desk keypair signs to authorize withdrawal
         only_owner(&ctx.accounts.desk, &ctx.accounts.owner.key())?;
         require!(ctx.accounts.desk_signer.key() == ctx.accounts.desk.key(), OtcError::NotOwner);
+        // Accrued fees and escrowed bid deposits are not the owner's to withdraw
+        let remaining = ctx.accounts.desk_usdc_treasury.amount.checked_sub(amount).ok_or(OtcError::Overflow)?;
+        let reserved = ctx.accounts.desk.commission_owed_usdc.checked_add(ctx.accounts.desk.escrowed_usdc).ok_or(OtcError::Overflow)?;
+        require!(remaining >= reserved, OtcError::CommissionOwed);
         let cpi_accounts = TransferChecked {
             from: ctx.accounts.desk_usdc_treasury.to_account_info(),
             to: ctx.accounts.to_usdc_ata.to_account_info(),
@@ -1077,6 +2602,7 @@ pub mod otc {
         let current = ctx.accounts.desk.to_account_info().lamports();
         let after = current.checked_sub(lamports).ok_or(OtcError::Overflow)?;
         require!(after >= min_rent, OtcError::BadState);
+        require!(after - min_rent >= ctx.accounts.desk.commission_owed_lamports, OtcError::CommissionOwed);
         
         **ctx.accounts.desk.to_account_info().try_borrow_mut_lamports()? -= lamports;
         **ctx.accounts.to.to_account_info().try_borrow_mut_lamports()? += lamports;
@@ -1099,6 +2625,28 @@ pub mod otc {
         Ok(())
     }
 
+    /// Set the protocol fee and referrer share taken from each deal, max 10% each (owner only)
+    /// Rates are snapshotted on the offer at creation; the referrer share only applies when one is passed
+    pub fn set_fee_schedule(ctx: Context<OnlyOwnerDesk>, protocol_treasury: Pubkey, protocol_fee_bps: u16, referrer_bps: u16) -> Result<()> {
+        require!(protocol_fee_bps <= 1000 && referrer_bps <= 1000, OtcError::CommissionRange);
+        require!(protocol_fee_bps == 0 || protocol_treasury != Pubkey::default(), OtcError::BadState);
+        let desk = &mut ctx.accounts.desk;
+        desk.protocol_treasury = protocol_treasury;
+        desk.protocol_fee_bps = protocol_fee_bps;
+        desk.referrer_bps = referrer_bps;
+        emit!(FeeScheduleUpdated { protocol_treasury, protocol_fee_bps, referrer_bps });
+        Ok(())
+    }
+
+    /// Set or clear (no points) the maximum discount by lockup days curve for direct offers (owner only)
+    pub fn set_discount_curve(ctx: Context<OnlyOwnerDesk>, points: Vec<DiscountCurvePoint>) -> Result<()> {
+        validate_discount_curve(&points)?;
+        let desk_key = ctx.accounts.desk.key();
+        ctx.accounts.desk.discount_curve = points.clone();
+        emit!(DiscountCurveSet { target: desk_key, points });
+        Ok(())
+    }
+
     pub fn emergency_refund_sol(ctx: Context<EmergencyRefundSol>, _offer_id: u64) -> Result<()> {
         let desk = &ctx.accounts.desk;
         require!(desk.emergency_refund_enabled, OtcError::BadState);
@@ -1106,6 +2654,7 @@ pub mod otc {
         let offer = &mut ctx.accounts.offer;
         require!(offer.paid && !offer.fulfilled && !offer.cancelled, OtcError::BadState);
         require!(offer.currency == 0, OtcError::BadState); // SOL payment
+        require!(!offer.seller_paid, OtcError::NotRefundable);
         
         let now = Clock::get()?.unix_timestamp;
         let deadline = offer.created_at.checked_add(desk.emergency_refund_deadline_secs).ok_or(OtcError::Overflow)?;
@@ -1127,10 +2676,22 @@ pub mod otc {
         offer.cancelled = true;
         
         // Note: desk.token_reserved is deprecated - multi-token model doesn't use it
+        let offer_key = offer.key();
+        let desk_key = ctx.accounts.desk.key();
+        reverse_settlement_fees(
+            &mut ctx.accounts.desk,
+            desk_key,
+            &mut ctx.accounts.offer,
+            offer_key,
+            ctx.accounts.agent_commission.as_deref_mut(),
+            ctx.accounts.protocol_fee_accrual.as_deref_mut(),
+            ctx.accounts.referrer_fee_accrual.as_deref_mut(),
+        )?;
         
         // Refund SOL to payer
-        **ctx.accounts.desk.to_account_info().try_borrow_mut_lamports()? -= offer.amount_paid;
-        **ctx.accounts.payer_refund.to_account_info().try_borrow_mut_lamports()? += offer.amount_paid;
+        let amount_paid = ctx.accounts.offer.amount_paid;
+        **ctx.accounts.desk.to_account_info().try_borrow_mut_lamports()? -= amount_paid;
+        **ctx.accounts.payer_refund.to_account_info().try_borrow_mut_lamports()? += amount_paid;
         
         Ok(())
     }
@@ -1142,6 +2703,7 @@ pub mod otc {
         let offer = &mut ctx.accounts.offer;
         require!(offer.paid && !offer.fulfilled && !offer.cancelled, OtcError::BadState);
         require!(offer.currency == 1, OtcError::BadState); // USDC payment
+        require!(!offer.seller_paid, OtcError::NotRefundable); // a refund would pay the payment out twice
         
         let now = Clock::get()?.unix_timestamp;
         let deadline = offer.created_at.checked_add(desk.emergency_refund_deadline_secs).ok_or(OtcError::Overflow)?;
@@ -1163,6 +2725,17 @@ pub mod otc {
         offer.cancelled = true;
         
         // Note: desk.token_reserved is deprecated - multi-token model doesn't use it
+        let offer_key = offer.key();
+        let desk_key = ctx.accounts.desk.key();
+        reverse_settlement_fees(
+            &mut ctx.accounts.desk,
+            desk_key,
+            &mut ctx.accounts.offer,
+            offer_key,
+            ctx.accounts.agent_commission.as_deref_mut(),
+            ctx.accounts.protocol_fee_accrual.as_deref_mut(),
+            ctx.accounts.referrer_fee_accrual.as_deref_mut(),
+        )?;
         
         // Refund USDC to payer
         let cpi_accounts = TransferChecked {
@@ -1172,7 +2745,7 @@ pub mod otc {
             mint: ctx.accounts.usdc_mint.to_account_info(),
         };
         let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
-        transfer_checked(cpi_ctx, offer.amount_paid, ctx.accounts.desk.usdc_decimals)?;
+        transfer_checked(cpi_ctx, ctx.accounts.offer.amount_paid, ctx.accounts.desk.usdc_decimals)?;
         
         Ok(())
     }
@@ -1180,65 +2753,446 @@ pub mod otc {
 }
 
 #[derive(Accounts)]
-pub struct InitDesk<'info> {
+pub struct InitDesk<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub owner: Signer<'info>,
+    /// CHECK: Agent can be any account
+    pub agent: UncheckedAccount<'info>,
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    pub system_program: Program<'info, System>,
+    #[account(init, payer = payer, space = 8 + Desk::SIZE)]
+    pub desk: Account<'info, Desk>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterToken<'info> {
+    pub desk: Account<'info, Desk>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init, 
+        payer = payer, 
+        space = 8 + TokenRegistry::SIZE,
+        seeds = [b"registry", desk.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub token_registry: Account<'info, TokenRegistry>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: program-owned Desk, TokenRegistry, Consignment or Offer, identified by its discriminator
+    #[account(mut, owner = crate::ID @ OtcError::BadState)]
+    pub account: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateConsignment<'info> {
+    #[account(mut)]
+    pub desk: Account<'info, Desk>,
+    #[account(mut)]
+    pub consigner: Signer<'info>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, constraint = consigner_token_ata.mint == token_mint.key() @ OtcError::BadState, constraint = consigner_token_ata.owner == consigner.key() @ OtcError::BadState)]
+    pub consigner_token_ata: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, constraint = desk_token_treasury.mint == token_mint.key() @ OtcError::BadState, constraint = desk_token_treasury.owner == desk.key() @ OtcError::BadState)]
+    pub desk_token_treasury: InterfaceAccount<'info, TokenAccount>,
+    #[account(init, payer = consigner, space = 8 + Consignment::SIZE)]
+    pub consignment: Account<'info, Consignment>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateOfferFromConsignment<'info> {
+    #[account(mut)]
+    pub desk: Account<'info, Desk>,
+    #[account(mut, constraint = consignment.desk == desk.key() @ OtcError::BadState)]
+    pub consignment: Account<'info, Consignment>,
+    #[account(constraint = token_registry.desk == desk.key() @ OtcError::BadState)]
+    pub token_registry: Account<'info, TokenRegistry>,
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+    #[account(init_if_needed, payer = beneficiary, space = 8 + Offer::SIZE)]
+    pub offer: Account<'info, Offer>,
+    /// Optional referrer credited with desk.referrer_bps at fulfillment
+    /// CHECK: only the key is recorded
+    pub referrer: Option<UncheckedAccount<'info>>,
+    /// Desk commission policy - required once set_commission_policy has been called
+    #[account(seeds = [b"commission_policy", desk.key().as_ref()], bump)]
+    pub commission_policy: Option<Account<'info, CommissionPolicy>>,
+    /// Compliance accounts - required when desk.compliance_required_mask gates this instruction
+    #[account(seeds = [b"compliance", desk.key().as_ref()], bump)]
+    pub compliance_config: Option<Account<'info, ComplianceConfig>>,
+    /// CHECK: beneficiary's attestation PDA, always passed so a denylisting cannot be skipped; may be uninitialized
+    #[account(seeds = [b"attestation", desk.key().as_ref(), beneficiary.key().as_ref()], bump)]
+    pub beneficiary_attestation: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(consignment_id: u64, token_amount: u64, currency: u8)]
+pub struct BuyFromConsignment<'info> {
+    #[account(mut)]
+    pub desk: Account<'info, Desk>,
+    #[account(mut, constraint = consignment.desk == desk.key() @ OtcError::BadState)]
+    pub consignment: Account<'info, Consignment>,
+    #[account(constraint = token_registry.desk == desk.key() @ OtcError::BadState)]
+    pub token_registry: Account<'info, TokenRegistry>,
+    #[account(constraint = desk_token_treasury.mint == consignment.token_mint @ OtcError::BadState, constraint = desk_token_treasury.owner == desk.key() @ OtcError::BadState)]
+    pub desk_token_treasury: InterfaceAccount<'info, TokenAccount>,
+    /// Buyer - creates, pays for and is the beneficiary of the offer
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+    #[account(init, payer = beneficiary, space = 8 + Offer::SIZE)]
+    pub offer: Account<'info, Offer>,
+    /// USDC accounts - required for currency 1
+    #[account(constraint = usdc_mint.key() == desk.usdc_mint @ OtcError::BadState)]
+    pub usdc_mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(mut, constraint = desk_usdc_treasury.mint == desk.usdc_mint @ OtcError::BadState, constraint = desk_usdc_treasury.owner == desk.key() @ OtcError::BadState)]
+    pub desk_usdc_treasury: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, constraint = beneficiary_usdc_ata.mint == desk.usdc_mint @ OtcError::BadState, constraint = beneficiary_usdc_ata.owner == beneficiary.key() @ OtcError::BadState)]
+    pub beneficiary_usdc_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+    /// Commission accrual for desk.agent in the payment currency
+    #[account(
+        init_if_needed,
+        payer = beneficiary,
+        space = 8 + CommissionAccrual::SIZE,
+        seeds = [b"commission", desk.key().as_ref(), desk.agent.as_ref(), currency.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub agent_commission: Account<'info, CommissionAccrual>,
+    /// Fee accruals for desk.protocol_treasury and offer.referrer - required when that share is non-zero
+    #[account(mut)]
+    pub protocol_fee_accrual: Option<Account<'info, CommissionAccrual>>,
+    #[account(mut)]
+    pub referrer_fee_accrual: Option<Account<'info, CommissionAccrual>>,
+    /// Referrer credited with desk.referrer_bps of the deal
+    /// CHECK: only the key is recorded
+    pub referrer: Option<UncheckedAccount<'info>>,
+    /// Compliance accounts - required when desk.compliance_required_mask gates creation or fulfillment
+    #[account(seeds = [b"compliance", desk.key().as_ref()], bump)]
+    pub compliance_config: Option<Account<'info, ComplianceConfig>>,
+    /// CHECK: beneficiary's attestation PDA, always passed so a denylisting cannot be skipped; may be uninitialized
+    #[account(seeds = [b"attestation", desk.key().as_ref(), beneficiary.key().as_ref()], bump)]
+    pub beneficiary_attestation: UncheckedAccount<'info>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(quote: SignedQuote)]
+pub struct CreateOfferFromSignedQuote<'info> {
+    #[account(mut)]
+    pub desk: Account<'info, Desk>,
+    #[account(mut, constraint = consignment.desk == desk.key() @ OtcError::BadState)]
+    pub consignment: Account<'info, Consignment>,
+    #[account(constraint = token_registry.desk == desk.key() @ OtcError::BadState)]
+    pub token_registry: Account<'info, TokenRegistry>,
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+    #[account(init, payer = beneficiary, space = 8 + Offer::SIZE)]
+    pub offer: Account<'info, Offer>,
+    /// Optional referrer credited with desk.referrer_bps at fulfillment
+    /// CHECK: only the key is recorded
+    pub referrer: Option<UncheckedAccount<'info>>,
+    /// Replay guard - init fails if the nonce was already redeemed
+    #[account(
+        init,
+        payer = beneficiary,
+        space = 8 + UsedQuote::SIZE,
+        seeds = [b"quote", desk.key().as_ref(), &quote.nonce.to_le_bytes()],
+        bump
+    )]
+    pub used_quote: Account<'info, UsedQuote>,
+    /// CHECK: Instructions sysvar, address-checked
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+    /// Desk commission policy - required once set_commission_policy has been called
+    #[account(seeds = [b"commission_policy", desk.key().as_ref()], bump)]
+    pub commission_policy: Option<Account<'info, CommissionPolicy>>,
+    /// Compliance accounts - required when desk.compliance_required_mask gates this instruction
+    #[account(seeds = [b"compliance", desk.key().as_ref()], bump)]
+    pub compliance_config: Option<Account<'info, ComplianceConfig>>,
+    /// CHECK: beneficiary's attestation PDA, always passed so a denylisting cannot be skipped; may be uninitialized
+    #[account(seeds = [b"attestation", desk.key().as_ref(), beneficiary.key().as_ref()], bump)]
+    pub beneficiary_attestation: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConsignment<'info> {
+    pub desk: Account<'info, Desk>,
+    #[account(mut, constraint = consignment.desk == desk.key() @ OtcError::BadState, constraint = consignment.consigner == consigner.key() @ OtcError::NotOwner)]
+    pub consignment: Account<'info, Consignment>,
+    pub consigner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateAuction<'info> {
+    pub desk: Account<'info, Desk>,
+    #[account(mut, constraint = consignment.desk == desk.key() @ OtcError::BadState, constraint = consignment.consigner == consigner.key() @ OtcError::NotOwner)]
+    pub consignment: Account<'info, Consignment>,
     #[account(mut)]
-    pub payer: Signer<'info>,
-    pub owner: Signer<'info>,
-    /// CHECK: Agent can be any account
-    pub agent: UncheckedAccount<'info>,
+    pub consigner: Signer<'info>,
+    #[account(
+        init,
+        payer = consigner,
+        space = 8 + Auction::SIZE,
+        seeds = [b"auction", consignment.key().as_ref()],
+        bump
+    )]
+    pub auction: Account<'info, Auction>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CommitBid<'info> {
+    #[account(mut)]
+    pub desk: Account<'info, Desk>,
+    #[account(mut, constraint = auction.desk == desk.key() @ OtcError::BadState)]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        init,
+        payer = bidder,
+        space = 8 + SealedBid::SIZE,
+        seeds = [b"sealed_bid", auction.key().as_ref(), bidder.key().as_ref()],
+        bump
+    )]
+    pub sealed_bid: Account<'info, SealedBid>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    #[account(constraint = usdc_mint.key() == desk.usdc_mint @ OtcError::BadState)]
     pub usdc_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, constraint = bidder_usdc_ata.mint == desk.usdc_mint @ OtcError::BadState, constraint = bidder_usdc_ata.owner == bidder.key() @ OtcError::BadState)]
+    pub bidder_usdc_ata: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, constraint = desk_usdc_treasury.mint == desk.usdc_mint @ OtcError::BadState, constraint = desk_usdc_treasury.owner == desk.key() @ OtcError::BadState)]
+    pub desk_usdc_treasury: InterfaceAccount<'info, TokenAccount>,
+    /// Compliance accounts - the config is required when desk.compliance_required_mask gates consignment offers
+    #[account(seeds = [b"compliance", desk.key().as_ref()], bump)]
+    pub compliance_config: Option<Account<'info, ComplianceConfig>>,
+    /// CHECK: bidder's attestation PDA, always passed so a denylisting cannot be skipped; may be uninitialized
+    #[account(seeds = [b"attestation", desk.key().as_ref(), bidder.key().as_ref()], bump)]
+    pub bidder_attestation: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
-    #[account(init, payer = payer, space = 8 + Desk::SIZE)]
+}
+
+#[derive(Accounts)]
+pub struct RevealBid<'info> {
     pub desk: Account<'info, Desk>,
+    #[account(mut, constraint = auction.desk == desk.key() @ OtcError::BadState)]
+    pub auction: Account<'info, Auction>,
+    #[account(constraint = consignment.key() == auction.consignment @ OtcError::BadState)]
+    pub consignment: Account<'info, Consignment>,
+    #[account(constraint = token_registry.desk == desk.key() @ OtcError::BadState, constraint = token_registry.token_mint == consignment.token_mint @ OtcError::BadState)]
+    pub token_registry: Account<'info, TokenRegistry>,
+    #[account(mut, constraint = sealed_bid.auction == auction.key() @ OtcError::BadState, constraint = sealed_bid.bidder == bidder.key() @ OtcError::BadState)]
+    pub sealed_bid: Account<'info, SealedBid>,
+    pub bidder: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct RegisterToken<'info> {
+pub struct SettleAuction<'info> {
     pub desk: Account<'info, Desk>,
+    #[account(mut, constraint = auction.desk == desk.key() @ OtcError::BadState)]
+    pub auction: Account<'info, Auction>,
+    #[account(mut, constraint = consignment.key() == auction.consignment @ OtcError::BadState)]
+    pub consignment: Account<'info, Consignment>,
+    #[account(constraint = token_registry.desk == desk.key() @ OtcError::BadState)]
+    pub token_registry: Account<'info, TokenRegistry>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimAuctionOffer<'info> {
     #[account(mut)]
-    pub payer: Signer<'info>,
+    pub desk: Account<'info, Desk>,
+    #[account(constraint = auction.desk == desk.key() @ OtcError::BadState)]
+    pub auction: Account<'info, Auction>,
+    #[account(constraint = consignment.key() == auction.consignment @ OtcError::BadState)]
+    pub consignment: Account<'info, Consignment>,
+    #[account(constraint = token_registry.desk == desk.key() @ OtcError::BadState)]
+    pub token_registry: Account<'info, TokenRegistry>,
+    #[account(mut, constraint = sealed_bid.auction == auction.key() @ OtcError::BadState, constraint = sealed_bid.bidder == bidder.key() @ OtcError::BadState)]
+    pub sealed_bid: Account<'info, SealedBid>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    #[account(init, payer = bidder, space = 8 + Offer::SIZE)]
+    pub offer: Account<'info, Offer>,
+    /// Compliance accounts - the config is required when desk.compliance_required_mask gates consignment offers
+    #[account(seeds = [b"compliance", desk.key().as_ref()], bump)]
+    pub compliance_config: Option<Account<'info, ComplianceConfig>>,
+    /// CHECK: bidder's attestation PDA, always passed so a denylisting cannot be skipped; may be uninitialized
+    #[account(seeds = [b"attestation", desk.key().as_ref(), bidder.key().as_ref()], bump)]
+    pub bidder_attestation: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseBidDeposit<'info> {
+    #[account(mut)]
+    pub desk: Account<'info, Desk>,
+    #[account(constraint = desk_signer.key() == desk.key() @ OtcError::NotOwner)]
+    pub desk_signer: Signer<'info>,
+    #[account(constraint = auction.desk == desk.key() @ OtcError::BadState)]
+    pub auction: Account<'info, Auction>,
+    /// Takes back an allocation left unclaimed past claim_end_at
+    #[account(mut, constraint = consignment.key() == auction.consignment @ OtcError::BadState)]
+    pub consignment: Account<'info, Consignment>,
+    #[account(mut, close = bidder, constraint = sealed_bid.auction == auction.key() @ OtcError::BadState)]
+    pub sealed_bid: Account<'info, SealedBid>,
+    /// Offer claimed by a winning bid - required once the offer exists
+    #[account(mut, constraint = offer.key() == sealed_bid.offer @ OtcError::BadState)]
+    pub offer: Option<Account<'info, Offer>>,
+    /// CHECK: receives the bid account rent, must be the bidder
+    #[account(mut, constraint = bidder.key() == sealed_bid.bidder @ OtcError::BadState)]
+    pub bidder: UncheckedAccount<'info>,
+    #[account(constraint = usdc_mint.key() == desk.usdc_mint @ OtcError::BadState)]
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, constraint = desk_usdc_treasury.mint == desk.usdc_mint @ OtcError::BadState, constraint = desk_usdc_treasury.owner == desk.key() @ OtcError::BadState)]
+    pub desk_usdc_treasury: InterfaceAccount<'info, TokenAccount>,
+    /// Bidder's USDC account for revealed bids, consigner's for forfeited ones
+    #[account(mut, constraint = recipient_usdc_ata.mint == desk.usdc_mint @ OtcError::BadState)]
+    pub recipient_usdc_ata: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CreateBid<'info> {
+    #[account(mut)]
+    pub desk: Account<'info, Desk>,
+    #[account(constraint = token_registry.desk == desk.key() @ OtcError::BadState)]
+    pub token_registry: Account<'info, TokenRegistry>,
+    #[account(init, payer = buyer, space = 8 + Bid::SIZE)]
+    pub bid: Account<'info, Bid>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(constraint = usdc_mint.key() == desk.usdc_mint @ OtcError::BadState)]
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, constraint = buyer_usdc_ata.mint == desk.usdc_mint @ OtcError::BadState, constraint = buyer_usdc_ata.owner == buyer.key() @ OtcError::BadState)]
+    pub buyer_usdc_ata: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, constraint = desk_usdc_treasury.mint == desk.usdc_mint @ OtcError::BadState, constraint = desk_usdc_treasury.owner == desk.key() @ OtcError::BadState)]
+    pub desk_usdc_treasury: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FillBid<'info> {
+    #[account(mut)]
+    pub desk: Account<'info, Desk>,
+    #[account(constraint = desk_signer.key() == desk.key() @ OtcError::NotOwner)]
+    pub desk_signer: Signer<'info>,
+    #[account(mut, constraint = bid.desk == desk.key() @ OtcError::BadState)]
+    pub bid: Account<'info, Bid>,
+    #[account(constraint = token_registry.desk == desk.key() @ OtcError::BadState, constraint = token_registry.token_mint == bid.token_mint @ OtcError::BadState)]
+    pub token_registry: Account<'info, TokenRegistry>,
+    #[account(init, payer = seller, space = 8 + Offer::SIZE)]
+    pub offer: Account<'info, Offer>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    /// Seller's consignment to sell from; when absent the tokens come from seller_token_ata
+    #[account(mut, constraint = consignment.desk == desk.key() @ OtcError::BadState, constraint = consignment.token_mint == bid.token_mint @ OtcError::BadState)]
+    pub consignment: Option<Account<'info, Consignment>>,
+    #[account(mut, constraint = seller_token_ata.mint == bid.token_mint @ OtcError::BadState, constraint = seller_token_ata.owner == seller.key() @ OtcError::BadState)]
+    pub seller_token_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(constraint = token_mint.key() == bid.token_mint @ OtcError::BadState)]
     pub token_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, constraint = desk_token_treasury.mint == bid.token_mint @ OtcError::BadState, constraint = desk_token_treasury.owner == desk.key() @ OtcError::BadState)]
+    pub desk_token_treasury: InterfaceAccount<'info, TokenAccount>,
+    #[account(constraint = usdc_mint.key() == desk.usdc_mint @ OtcError::BadState)]
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, constraint = desk_usdc_treasury.mint == desk.usdc_mint @ OtcError::BadState, constraint = desk_usdc_treasury.owner == desk.key() @ OtcError::BadState)]
+    pub desk_usdc_treasury: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, constraint = seller_usdc_ata.mint == desk.usdc_mint @ OtcError::BadState, constraint = seller_usdc_ata.owner == seller.key() @ OtcError::BadState)]
+    pub seller_usdc_ata: InterfaceAccount<'info, TokenAccount>,
+    /// Commission accrual for desk.agent in USDC
     #[account(
-        init, 
-        payer = payer, 
-        space = 8 + TokenRegistry::SIZE,
-        seeds = [b"registry", desk.key().as_ref(), token_mint.key().as_ref()],
+        init_if_needed,
+        payer = seller,
+        space = 8 + CommissionAccrual::SIZE,
+        seeds = [b"commission", desk.key().as_ref(), desk.agent.as_ref(), 1u8.to_le_bytes().as_ref()],
         bump
     )]
-    pub token_registry: Account<'info, TokenRegistry>,
+    pub agent_commission: Account<'info, CommissionAccrual>,
+    /// Fee accrual for desk.protocol_treasury - required when the protocol fee is non-zero
+    #[account(mut)]
+    pub protocol_fee_accrual: Option<Account<'info, CommissionAccrual>>,
+    /// Compliance accounts - the config is required when desk.compliance_required_mask gates offer creation or fulfillment
+    #[account(seeds = [b"compliance", desk.key().as_ref()], bump)]
+    pub compliance_config: Option<Account<'info, ComplianceConfig>>,
+    /// CHECK: bid buyer's attestation PDA, always passed so a denylisting cannot be skipped; may be uninitialized
+    #[account(seeds = [b"attestation", desk.key().as_ref(), bid.buyer.as_ref()], bump)]
+    pub buyer_attestation: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct CreateConsignment<'info> {
+pub struct CancelBid<'info> {
+    #[account(mut)]
+    pub desk: Account<'info, Desk>,
+    #[account(constraint = desk_signer.key() == desk.key() @ OtcError::NotOwner)]
+    pub desk_signer: Signer<'info>,
+    #[account(mut, close = buyer, constraint = bid.desk == desk.key() @ OtcError::BadState, constraint = bid.buyer == buyer.key() @ OtcError::NotOwner)]
+    pub bid: Account<'info, Bid>,
     #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(constraint = usdc_mint.key() == desk.usdc_mint @ OtcError::BadState)]
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, constraint = desk_usdc_treasury.mint == desk.usdc_mint @ OtcError::BadState, constraint = desk_usdc_treasury.owner == desk.key() @ OtcError::BadState)]
+    pub desk_usdc_treasury: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, constraint = buyer_usdc_ata.mint == desk.usdc_mint @ OtcError::BadState, constraint = buyer_usdc_ata.owner == buyer.key() @ OtcError::BadState)]
+    pub buyer_usdc_ata: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct TopUpConsignment<'info> {
     pub desk: Account<'info, Desk>,
+    #[account(mut, constraint = consignment.desk == desk.key() @ OtcError::BadState, constraint = consignment.consigner == consigner.key() @ OtcError::NotOwner)]
+    pub consignment: Account<'info, Consignment>,
     #[account(mut)]
     pub consigner: Signer<'info>,
+    #[account(constraint = token_mint.key() == consignment.token_mint @ OtcError::BadState)]
     pub token_mint: InterfaceAccount<'info, Mint>,
     #[account(mut, constraint = consigner_token_ata.mint == token_mint.key() @ OtcError::BadState, constraint = consigner_token_ata.owner == consigner.key() @ OtcError::BadState)]
     pub consigner_token_ata: InterfaceAccount<'info, TokenAccount>,
     #[account(mut, constraint = desk_token_treasury.mint == token_mint.key() @ OtcError::BadState, constraint = desk_token_treasury.owner == desk.key() @ OtcError::BadState)]
     pub desk_token_treasury: InterfaceAccount<'info, TokenAccount>,
-    #[account(init, payer = consigner, space = 8 + Consignment::SIZE)]
-    pub consignment: Account<'info, Consignment>,
     pub token_program: Interface<'info, TokenInterface>,
-    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct CreateOfferFromConsignment<'info> {
-    #[account(mut)]
+pub struct ExpireConsignment<'info> {
     pub desk: Account<'info, Desk>,
     #[account(mut, constraint = consignment.desk == desk.key() @ OtcError::BadState)]
     pub consignment: Account<'info, Consignment>,
-    #[account(constraint = token_registry.desk == desk.key() @ OtcError::BadState)]
-    pub token_registry: Account<'info, TokenRegistry>,
-    #[account(mut)]
-    pub beneficiary: Signer<'info>,
-    #[account(init_if_needed, payer = beneficiary, space = 8 + Offer::SIZE)]
-    pub offer: Account<'info, Offer>,
-    pub system_program: Program<'info, System>,
+    /// Anyone can crank an expired consignment
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SweepExpiredConsignment<'info> {
+    pub desk: Account<'info, Desk>,
+    #[account(constraint = desk_signer.key() == desk.key() @ OtcError::NotOwner)]
+    pub desk_signer: Signer<'info>,
+    #[account(mut, constraint = consignment.desk == desk.key() @ OtcError::BadState)]
+    pub consignment: Account<'info, Consignment>,
+    #[account(constraint = token_mint.key() == consignment.token_mint @ OtcError::BadState)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, constraint = desk_token_treasury.mint == consignment.token_mint @ OtcError::BadState, constraint = desk_token_treasury.owner == desk.key() @ OtcError::BadState)]
+    pub desk_token_treasury: InterfaceAccount<'info, TokenAccount>,
+    /// SECURITY: Inventory can only go back to the consigner
+    #[account(mut, constraint = consigner_token_ata.mint == consignment.token_mint @ OtcError::BadState, constraint = consigner_token_ata.owner == consignment.consigner @ OtcError::BadState)]
+    pub consigner_token_ata: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
@@ -1370,9 +3324,112 @@ pub struct CreateOffer<'info> {
     pub beneficiary: Signer<'info>,
     #[account(init_if_needed, payer = beneficiary, space = 8 + Offer::SIZE)]
     pub offer: Account<'info, Offer>,
+    /// Optional referrer credited with desk.referrer_bps at fulfillment
+    /// CHECK: only the key is recorded
+    pub referrer: Option<UncheckedAccount<'info>>,
+    /// Compliance accounts - required when desk.compliance_required_mask gates this instruction
+    #[account(seeds = [b"compliance", desk.key().as_ref()], bump)]
+    pub compliance_config: Option<Account<'info, ComplianceConfig>>,
+    /// CHECK: beneficiary's attestation PDA, always passed so a denylisting cannot be skipped; may be uninitialized
+    #[account(seeds = [b"attestation", desk.key().as_ref(), beneficiary.key().as_ref()], bump)]
+    pub beneficiary_attestation: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetComplianceConfig<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(mut, has_one = owner)]
+    pub desk: Account<'info, Desk>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + ComplianceConfig::SIZE,
+        seeds = [b"compliance", desk.key().as_ref()],
+        bump
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetCommissionPolicy<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(mut, has_one = owner)]
+    pub desk: Account<'info, Desk>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + CommissionPolicy::SIZE,
+        seeds = [b"commission_policy", desk.key().as_ref()],
+        bump
+    )]
+    pub commission_policy: Account<'info, CommissionPolicy>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetComplianceIssuer<'info> {
+    pub owner: Signer<'info>,
+    #[account(has_one = owner)]
+    pub desk: Account<'info, Desk>,
+    #[account(mut, seeds = [b"compliance", desk.key().as_ref()], bump)]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct SetAttestation<'info> {
+    pub desk: Account<'info, Desk>,
+    #[account(seeds = [b"compliance", desk.key().as_ref()], bump)]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    /// Desk owner or a compliance issuer - validated in instruction
+    #[account(mut)]
+    pub issuer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = issuer,
+        space = 8 + Attestation::SIZE,
+        seeds = [b"attestation", desk.key().as_ref(), wallet.as_ref()],
+        bump
+    )]
+    pub attestation: Account<'info, Attestation>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct CounterOfferTerms<'info> {
+    pub desk: Account<'info, Desk>,
+    #[account(mut, constraint = offer.desk == desk.key() @ OtcError::BadState)]
+    pub offer: Account<'info, Offer>,
+    #[account(constraint = consignment.desk == desk.key() @ OtcError::BadState, constraint = consignment.id == offer.consignment_id @ OtcError::BadState)]
+    pub consignment: Account<'info, Consignment>,
+    pub proposer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RespondToCounter<'info> {
+    pub desk: Account<'info, Desk>,
+    #[account(mut, constraint = offer.desk == desk.key() @ OtcError::BadState, constraint = offer.beneficiary == beneficiary.key() @ OtcError::NotOwner)]
+    pub offer: Account<'info, Offer>,
+    #[account(mut, constraint = consignment.desk == desk.key() @ OtcError::BadState, constraint = consignment.id == offer.consignment_id @ OtcError::BadState)]
+    pub consignment: Account<'info, Consignment>,
+    #[account(constraint = token_registry.desk == desk.key() @ OtcError::BadState)]
+    pub token_registry: Account<'info, TokenRegistry>,
+    /// Desk commission policy - required once set_commission_policy has been called
+    #[account(seeds = [b"commission_policy", desk.key().as_ref()], bump)]
+    pub commission_policy: Option<Account<'info, CommissionPolicy>>,
+    pub beneficiary: Signer<'info>,
+    /// Compliance accounts - the config is required when desk.compliance_required_mask gates consignment offers
+    #[account(seeds = [b"compliance", desk.key().as_ref()], bump)]
+    pub compliance_config: Option<Account<'info, ComplianceConfig>>,
+    /// CHECK: beneficiary's attestation PDA, always passed so a denylisting cannot be skipped; may be uninitialized
+    #[account(seeds = [b"attestation", desk.key().as_ref(), beneficiary.key().as_ref()], bump)]
+    pub beneficiary_attestation: UncheckedAccount<'info>,
+}
+
 #[derive(Accounts)]
 pub struct ApproveOffer<'info> {
     pub desk: Account<'info, Desk>,
@@ -1402,12 +3459,32 @@ pub struct CancelOfferWithConsignment<'info> {
     pub caller: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct CleanupOffer<'info> {
+    pub desk: Account<'info, Desk>,
+    #[account(mut, constraint = offer.desk == desk.key() @ OtcError::BadState)]
+    pub offer: Account<'info, Offer>,
+    /// Consignment the offer came from - required to cancel an expired consignment offer
+    #[account(mut, constraint = consignment.desk == desk.key() @ OtcError::BadState, constraint = consignment.id == offer.consignment_id @ OtcError::BadState)]
+    pub consignment: Option<Account<'info, Consignment>>,
+    /// CHECK: Receives the offer rent - must be the account that paid it
+    #[account(mut, constraint = rent_recipient.key() == offer.rent_payer @ OtcError::BadState)]
+    pub rent_recipient: UncheckedAccount<'info>,
+    /// Anyone can crank cleanup
+    pub caller: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct FulfillOfferUsdc<'info> {
     #[account(mut)]
     pub desk: Account<'info, Desk>,
     #[account(mut, constraint = offer.desk == desk.key() @ OtcError::BadState)]
     pub offer: Account<'info, Offer>,
+    /// Consignment and registry the offer is priced from - required when the offer carries a reserve price
+    #[account(constraint = consignment.desk == desk.key() @ OtcError::BadState, constraint = consignment.id == offer.consignment_id @ OtcError::BadState)]
+    pub consignment: Option<Account<'info, Consignment>>,
+    #[account(constraint = token_registry.desk == desk.key() @ OtcError::BadState, constraint = token_registry.token_mint == offer.token_mint @ OtcError::BadState)]
+    pub token_registry: Option<Account<'info, TokenRegistry>>,
     pub usdc_mint: InterfaceAccount<'info, Mint>,
     /// Token treasury - must match the token_mint in the offer
     #[account(mut, constraint = desk_token_treasury.mint == offer.token_mint, constraint = desk_token_treasury.owner == desk.key())]
@@ -1416,14 +3493,32 @@ pub struct FulfillOfferUsdc<'info> {
     pub desk_usdc_treasury: InterfaceAccount<'info, TokenAccount>,
     #[account(mut, constraint = payer_usdc_ata.mint == desk.usdc_mint, constraint = payer_usdc_ata.owner == payer.key())]
     pub payer_usdc_ata: InterfaceAccount<'info, TokenAccount>,
-    /// Agent USDC account for receiving commission (optional - only needed if commission > 0)
-    /// SECURITY: Validated in instruction to be owned by desk.agent to prevent commission theft
+    /// Commission accrual for desk.agent in USDC
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CommissionAccrual::SIZE,
+        seeds = [b"commission", desk.key().as_ref(), desk.agent.as_ref(), offer.currency.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub agent_commission: Account<'info, CommissionAccrual>,
+    /// Fee accruals for desk.protocol_treasury and offer.referrer - required when that share is non-zero
+    #[account(mut)]
+    pub protocol_fee_accrual: Option<Account<'info, CommissionAccrual>>,
     #[account(mut)]
-    pub agent_usdc_ata: Option<InterfaceAccount<'info, TokenAccount>>,
-    /// Desk signer for authorizing commission transfer from treasury
+    pub referrer_fee_accrual: Option<Account<'info, CommissionAccrual>>,
     pub desk_signer: Signer<'info>,
     #[account(mut)]
     pub payer: Signer<'info>,
+    /// Compliance accounts - the config is required when desk.compliance_required_mask gates fulfillment
+    #[account(seeds = [b"compliance", desk.key().as_ref()], bump)]
+    pub compliance_config: Option<Account<'info, ComplianceConfig>>,
+    /// CHECK: offer beneficiary's attestation PDA, always passed so a denylisting cannot be skipped; may be uninitialized
+    #[account(seeds = [b"attestation", desk.key().as_ref(), offer.beneficiary.as_ref()], bump)]
+    pub beneficiary_attestation: UncheckedAccount<'info>,
+    /// CHECK: payer's attestation PDA, screened like the beneficiary's when someone else pays; may be uninitialized
+    #[account(seeds = [b"attestation", desk.key().as_ref(), payer.key().as_ref()], bump)]
+    pub payer_attestation: UncheckedAccount<'info>,
     pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
@@ -1434,17 +3529,40 @@ pub struct FulfillOfferSol<'info> {
     pub desk: Account<'info, Desk>,
     #[account(mut, constraint = offer.desk == desk.key() @ OtcError::BadState)]
     pub offer: Account<'info, Offer>,
+    /// Consignment and registry the offer is priced from - required when the offer carries a reserve price
+    #[account(constraint = consignment.desk == desk.key() @ OtcError::BadState, constraint = consignment.id == offer.consignment_id @ OtcError::BadState)]
+    pub consignment: Option<Account<'info, Consignment>>,
+    #[account(constraint = token_registry.desk == desk.key() @ OtcError::BadState, constraint = token_registry.token_mint == offer.token_mint @ OtcError::BadState)]
+    pub token_registry: Option<Account<'info, TokenRegistry>>,
     /// Token treasury - must match the token_mint in the offer
     #[account(mut, constraint = desk_token_treasury.mint == offer.token_mint, constraint = desk_token_treasury.owner == desk.key())]
     pub desk_token_treasury: InterfaceAccount<'info, TokenAccount>,
-    /// Agent account for receiving SOL commission (optional - only needed if commission > 0)
-    /// CHECK: This is the agent's wallet address, we're just sending SOL to it
+    /// Commission accrual for desk.agent in SOL
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CommissionAccrual::SIZE,
+        seeds = [b"commission", desk.key().as_ref(), desk.agent.as_ref(), offer.currency.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub agent_commission: Account<'info, CommissionAccrual>,
+    /// Fee accruals for desk.protocol_treasury and offer.referrer - required when that share is non-zero
     #[account(mut)]
-    pub agent: Option<AccountInfo<'info>>,
-    /// Desk signer for authorizing lamport transfer
+    pub protocol_fee_accrual: Option<Account<'info, CommissionAccrual>>,
+    #[account(mut)]
+    pub referrer_fee_accrual: Option<Account<'info, CommissionAccrual>>,
     pub desk_signer: Signer<'info>,
     #[account(mut)]
     pub payer: Signer<'info>,
+    /// Compliance accounts - the config is required when desk.compliance_required_mask gates fulfillment
+    #[account(seeds = [b"compliance", desk.key().as_ref()], bump)]
+    pub compliance_config: Option<Account<'info, ComplianceConfig>>,
+    /// CHECK: offer beneficiary's attestation PDA, always passed so a denylisting cannot be skipped; may be uninitialized
+    #[account(seeds = [b"attestation", desk.key().as_ref(), offer.beneficiary.as_ref()], bump)]
+    pub beneficiary_attestation: UncheckedAccount<'info>,
+    /// CHECK: payer's attestation PDA, screened like the beneficiary's when someone else pays; may be uninitialized
+    #[account(seeds = [b"attestation", desk.key().as_ref(), payer.key().as_ref()], bump)]
+    pub payer_attestation: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
 
@@ -1466,6 +3584,78 @@ pub struct Claim<'info> {
     pub token_program: Interface<'info, TokenInterface>,
 }
 
+#[derive(Accounts)]
+pub struct BatchClaim<'info> {
+    pub desk: Account<'info, Desk>,
+    #[account(constraint = desk_signer.key() == desk.key() @ OtcError::NotOwner)]
+    pub desk_signer: Signer<'info>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, constraint = desk_token_treasury.mint == token_mint.key() @ OtcError::BadState, constraint = desk_token_treasury.owner == desk.key() @ OtcError::BadState)]
+    pub desk_token_treasury: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(currency: u8)]
+pub struct ClaimCommission<'info> {
+    #[account(mut)]
+    pub desk: Account<'info, Desk>,
+    #[account(constraint = desk_signer.key() == desk.key() @ OtcError::NotOwner)]
+    pub desk_signer: Signer<'info>,
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"commission", desk.key().as_ref(), recipient.key().as_ref(), currency.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub fee_accrual: Account<'info, CommissionAccrual>,
+    /// USDC accounts - required for currency 1
+    #[account(constraint = usdc_mint.key() == desk.usdc_mint @ OtcError::BadState)]
+    pub usdc_mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(mut, constraint = desk_usdc_treasury.mint == desk.usdc_mint @ OtcError::BadState, constraint = desk_usdc_treasury.owner == desk.key() @ OtcError::BadState)]
+    pub desk_usdc_treasury: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, constraint = recipient_usdc_ata.mint == desk.usdc_mint @ OtcError::BadState, constraint = recipient_usdc_ata.owner == recipient.key() @ OtcError::BadState)]
+    pub recipient_usdc_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+#[instruction(recipient: Pubkey, currency: u8)]
+pub struct OpenFeeAccount<'info> {
+    pub desk: Account<'info, Desk>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + CommissionAccrual::SIZE,
+        seeds = [b"commission", desk.key().as_ref(), recipient.as_ref(), currency.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub fee_accrual: Account<'info, CommissionAccrual>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteOffer<'info> {
+    pub desk: Account<'info, Desk>,
+    #[account(constraint = offer.desk == desk.key() @ OtcError::BadState)]
+    pub offer: Account<'info, Offer>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteOrder<'info> {
+    pub desk: Account<'info, Desk>,
+    #[account(constraint = consignment.desk == desk.key() @ OtcError::BadState)]
+    pub consignment: Account<'info, Consignment>,
+    #[account(constraint = token_registry.desk == desk.key() @ OtcError::BadState)]
+    pub token_registry: Account<'info, TokenRegistry>,
+    /// Desk commission policy - required once set_commission_policy has been called
+    #[account(seeds = [b"commission_policy", desk.key().as_ref()], bump)]
+    pub commission_policy: Option<Account<'info, CommissionPolicy>>,
+}
+
 #[derive(Accounts)]
 pub struct WithdrawTokens<'info> {
     pub owner: Signer<'info>,
@@ -1539,6 +3729,13 @@ pub struct EmergencyRefundSol<'info> {
     /// CHECK: payer to refund - validated against offer.payer in instruction
     #[account(mut, constraint = payer_refund.key() == offer.payer @ OtcError::BadState)]
     pub payer_refund: UncheckedAccount<'info>,
+    /// Fee accruals credited when the offer was paid - required when that share is non-zero
+    #[account(mut)]
+    pub agent_commission: Option<Account<'info, CommissionAccrual>>,
+    #[account(mut)]
+    pub protocol_fee_accrual: Option<Account<'info, CommissionAccrual>>,
+    #[account(mut)]
+    pub referrer_fee_accrual: Option<Account<'info, CommissionAccrual>>,
     pub system_program: Program<'info, System>,
 }
 
@@ -1555,6 +3752,13 @@ pub struct EmergencyRefundUsdc<'info> {
     pub desk_usdc_treasury: InterfaceAccount<'info, TokenAccount>,
     #[account(mut, constraint = payer_usdc_refund.owner == offer.payer @ OtcError::BadState)]
     pub payer_usdc_refund: InterfaceAccount<'info, TokenAccount>,
+    /// Fee accruals credited when the offer was paid - required when that share is non-zero
+    #[account(mut)]
+    pub agent_commission: Option<Account<'info, CommissionAccrual>>,
+    #[account(mut)]
+    pub protocol_fee_accrual: Option<Account<'info, CommissionAccrual>>,
+    #[account(mut)]
+    pub referrer_fee_accrual: Option<Account<'info, CommissionAccrual>>,
     pub token_program: Interface<'info, TokenInterface>,
 }
 
@@ -1588,13 +3792,95 @@ pub struct Desk {
     pub emergency_refund_enabled: bool,
     pub emergency_refund_deadline_secs: i64,
     pub p2p_commission_bps: u16,
+    pub compliance_required_mask: u8, // COMPLIANCE_* flags for instructions that need a buyer attestation
+    pub commission_owed_usdc: u64, // accrued but unclaimed fee shares held in the USDC treasury
+    pub commission_owed_lamports: u64, // accrued but unclaimed fee shares held in desk lamports
+    pub commission_policy_enabled: bool, // negotiated offers must use the commission_policy PDA
+    pub protocol_treasury: Pubkey,
+    pub protocol_fee_bps: u16,
+    pub referrer_bps: u16,
+    pub escrowed_usdc: u64, // sealed-bid deposits and bid escrow held in the USDC treasury
+    pub discount_curve: Vec<DiscountCurvePoint>, // max 8, for direct offers; empty = no curve
+    pub pyth_max_conf_bps: u16, // 0 = no confidence limit on update_prices_from_pyth
+    pub pyth_price_mode: PythPriceMode,
+    pub min_pyth_verification: PythVerification,
+}
+
+impl Desk { pub const SIZE: usize = 32+32+32+1+8+8+8+1+4+(32*32)+8+8+1+32+8+8+32+1+8+8+32+8+8+8+8+1+8+2+1+8+8+1+32+2+2+8+4+(4*MAX_DISCOUNT_CURVE_POINTS)+2+1+2; } // +2 for Pyth verification level
+
+/// Sizes of accounts created before compliance, fee shares, auctions and pricing modes were appended; see migrate_account
+pub const DESK_LEGACY_SIZE: usize = 32+32+32+1+8+8+8+1+4+(32*32)+8+8+1+32+8+8+32+1+8+8+32+8+8+8+8+1+8+2;
+pub const TOKEN_REGISTRY_LEGACY_SIZE: usize = 32+32+1+32+32+1+1+8+8+32+8+16+8+8+2+8;
+pub const CONSIGNMENT_LEGACY_SIZE: usize = 32+8+32+32+8+8+1+2+4+2+2+4+4+8+8+1+1+2+8+1+8;
+pub const OFFER_LEGACY_SIZE: usize = 32+8+32+1+8+32+8+2+8+8+8+2+8+1+1+1+1+1+32+8+2;
+const DESK_LEGACY_APPROVERS_OFFSET: usize = 32+32+32+1+8+8+8+1;
+
+pub const COMPLIANCE_CREATE_OFFER: u8 = 1;
+pub const COMPLIANCE_CREATE_OFFER_FROM_CONSIGNMENT: u8 = 2;
+pub const COMPLIANCE_FULFILL: u8 = 4;
+const COMPLIANCE_ALL: u8 = COMPLIANCE_CREATE_OFFER | COMPLIANCE_CREATE_OFFER_FROM_CONSIGNMENT | COMPLIANCE_FULFILL;
+pub const COMPLIANCE_TIERS: usize = 8;
+const MAX_COMPLIANCE_ISSUERS: usize = 16;
+
+#[account]
+pub struct ComplianceConfig {
+    pub desk: Pubkey,
+    pub issuers: Vec<Pubkey>, // max 16
+    pub tier_max_usd_8d: [u64; COMPLIANCE_TIERS], // max discounted USD per offer by tier, 0 = no cap
+}
+
+impl ComplianceConfig { pub const SIZE: usize = 32+4+(32*MAX_COMPLIANCE_ISSUERS)+(8*COMPLIANCE_TIERS); }
+
+#[account]
+pub struct Attestation {
+    pub desk: Pubkey,
+    pub wallet: Pubkey,
+    pub tier: u8,
+    pub expires_at: i64, // 0 = no expiry
+    pub denied: bool,
+    pub issuer: Pubkey,
+    pub updated_at: i64,
+}
+
+impl Attestation { pub const SIZE: usize = 32+32+1+8+1+32+8; }
+
+/// Fee share (agent commission, protocol fee or referrer rebate) credited at payment time
+/// and paid out by claim_commission
+#[account]
+pub struct CommissionAccrual {
+    pub desk: Pubkey,
+    pub recipient: Pubkey,
+    pub currency: u8,
+    pub accrued_amount: u64, // lifetime total
+    pub claimed_amount: u64,
 }
 
-impl Desk { pub const SIZE: usize = 32+32+32+1+8+8+8+1+4+(32*32)+8+8+1+32+8+8+32+1+8+8+32+8+8+8+8+1+8+2; } // +2 for p2p_commission_bps
+impl CommissionAccrual { pub const SIZE: usize = 32+32+1+8+8; }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
 pub enum PoolType { #[default] None, Raydium, Orca, PumpSwap }
 
+/// Settlement breakdown returned by the quote views, using the same rounding as fulfillment
+/// Amounts are in the payment currency's base units (USDC 6 decimals or lamports)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SettlementQuote {
+    pub gross_usd_8d: u64, // before discount
+    pub discounted_usd_8d: u64,
+    pub currency: u8,
+    pub payment_amount: u64, // rounded up, debited from the buyer
+    pub commission_amount: u64, // rounded down, paid to the agent
+    pub protocol_fee_amount: u64,
+    pub referrer_fee_amount: u64,
+    pub consigner_net_amount: u64,
+    pub unlock_time: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ClaimStatus { Claimed, Locked, AlreadyClaimed, NotClaimable, InvalidAccounts }
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FeeKind { Agent, Protocol, Referrer }
+
 #[account]
 pub struct TokenRegistry {
     pub desk: Pubkey,
@@ -1614,13 +3900,54 @@ pub struct TokenRegistry {
     pub twap_last_price: u64,
     pub max_twap_deviation_bps: u16,
     pub min_update_interval_secs: i64,
+    // Pyth update guard
+    pub pyth_max_conf_bps: u16, // 0 = no confidence limit
+    pub pyth_price_mode: PythPriceMode,
 }
 
 impl TokenRegistry { 
     // 32+32+1+32+32+1+1+8+8+32 = 179 (original)
     // + 8 (min_liquidity) + 16 (twap_cumulative) + 8 (twap_last_ts) + 8 (twap_last_price) + 2 (max_twap_dev) + 8 (min_update) = 50
-    // Total = 229
-    pub const SIZE: usize = 32+32+1+32+32+1+1+8+8+32+8+16+8+8+2+8;
+    // + 2 (pyth_max_conf_bps) + 1 (pyth_price_mode) = 3
+    // Total = 232
+    pub const SIZE: usize = 32+32+1+32+32+1+1+8+8+32+8+16+8+8+2+8+2+1;
+}
+
+/// Wormhole verification level of a Pyth price update (mirrors the receiver SDK's VerificationLevel)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PythVerification {
+    /// Verified by at least num_signatures guardians
+    Partial { num_signatures: u8 },
+    /// Verified by a guardian quorum
+    Full,
+}
+
+impl PythVerification {
+    fn level(self) -> VerificationLevel {
+        match self {
+            PythVerification::Partial { num_signatures } => VerificationLevel::Partial { num_signatures },
+            PythVerification::Full => VerificationLevel::Full,
+        }
+    }
+
+    fn from_level(level: VerificationLevel) -> Self {
+        match level {
+            VerificationLevel::Partial { num_signatures } => PythVerification::Partial { num_signatures },
+            VerificationLevel::Full => PythVerification::Full,
+        }
+    }
+}
+
+/// Which Pyth price an oracle update stores
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum PythPriceMode {
+    /// Latest aggregate price
+    #[default]
+    Spot,
+    /// Exponentially-weighted moving average price, checked against the EMA confidence
+    Ema,
+    /// Edge of the confidence band that favours the desk: price + conf for tokens, price - conf for SOL
+    Conservative,
 }
 
 #[account]
@@ -1646,9 +3973,54 @@ pub struct Consignment {
     pub max_time_to_execute_secs: i64,
     pub is_active: bool,
     pub created_at: i64,
+    pub expires_at: i64, // 0 = never expires
+    pub allowlist_root: [u8; 32], // merkle root of allowed beneficiaries for private consignments, zero = none
+    pub auction_start_at: i64,
+    pub auction_end_at: i64, // 0 = fixed discount, otherwise Dutch auction from min to max discount
+    pub tier_basis: TierBasis,
+    pub discount_tiers: Vec<DiscountTier>, // max 8, ascending by min_size; empty = single band/fixed discount
+    pub discount_curve: Vec<DiscountCurvePoint>, // max 8; empty = no curve
+    pub min_net_price_usd_8d: u64, // reserve: lowest discounted per-token price, 0 = none
+    pub fixed_price_usd_8d: u64, // consigner-set price per token, 0 = registry price
+    pub accepted_currencies: u8, // ACCEPT_* flags
+    pub is_withdrawn: bool, // closed by the consigner; restored inventory is not re-listed
+    pub auction_live: bool, // remaining inventory is reserved by an unsettled auction
 }
 
-impl Consignment { pub const SIZE: usize = 32+8+32+32+8+8+1+2+4+2+2+4+4+8+8+1+1+2+8+1+8; }
+impl Consignment { pub const SIZE: usize = 32+8+32+32+8+8+1+2+4+2+2+4+4+8+8+1+1+2+8+1+8+8+32+8+8+1+4+(12*MAX_DISCOUNT_TIERS)+4+(4*MAX_DISCOUNT_CURVE_POINTS)+8+8+1+1+1; } // +2 for withdrawn and auction flags
+
+/// Consignment accepted payment currencies, bit = 1 << currency code
+pub const ACCEPT_SOL: u8 = 1;
+pub const ACCEPT_USDC: u8 = 2;
+
+pub const MAX_DISCOUNT_TIERS: usize = 8;
+
+/// What a consignment's discount tiers are keyed on
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum TierBasis {
+    /// Token base units
+    #[default]
+    TokenAmount,
+    /// Undiscounted USD value (8 decimals) at the registry price
+    UsdNotional,
+}
+
+pub const MAX_DISCOUNT_CURVE_POINTS: usize = 8;
+
+/// Maximum discount allowed for a lockup of lockup_days
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct DiscountCurvePoint {
+    pub lockup_days: u16,
+    pub max_discount_bps: u16,
+}
+
+/// Discount band for orders of at least min_size
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct DiscountTier {
+    pub min_size: u64,
+    pub min_discount_bps: u16,
+    pub max_discount_bps: u16,
+}
 
 #[account]
 pub struct Offer {
@@ -1673,16 +4045,740 @@ pub struct Offer {
     pub payer: Pubkey,
     pub amount_paid: u64,
     pub agent_commission_bps: u16, // p2p_commission_bps for P2P (default 0.25%), 25-150 for negotiated deals
+    pub quote_expiry_secs: i64, // consignment.max_time_to_execute_secs, 0 = use desk.quote_expiry_secs
+    pub rent_payer: Pubkey, // receives the rent back when the offer is closed
+    pub protocol_fee_bps: u16, // desk.protocol_fee_bps at creation
+    pub referrer: Pubkey, // default = no referrer
+    pub referrer_bps: u16,
+    pub counter_round: u16, // number of counters proposed
+    pub counter_pending: bool, // counter_* awaits the beneficiary's response
+    pub counter_token_amount: u64,
+    pub counter_discount_bps: u16,
+    pub counter_lockup_secs: i64,
+    pub min_net_price_usd_8d: u64, // consignment reserve at creation, re-checked at fulfillment
+    // Fee shares credited at payment, reversed by an emergency refund
+    pub commission_recipient: Pubkey, // desk.agent at payment
+    pub commission_accrued: u64,
+    pub protocol_fee_recipient: Pubkey, // desk.protocol_treasury at payment
+    pub protocol_fee_accrued: u64,
+    pub referrer_fee_accrued: u64, // credited to referrer
+    pub auction_bid: Pubkey, // sealed bid whose deposit waits on this offer, default = none
+    pub seller_paid: bool, // the seller was paid out of the payment when it was made (bid fills)
+}
+
+impl Offer { pub const SIZE: usize = 32+8+32+1+8+32+8+2+8+8+8+2+8+1+1+1+1+1+32+8+2+8+32+2+32+2+2+1+8+2+8+8+32+8+32+8+8+32+1; } // +1 for seller_paid
+
+/// Off-chain quote signed by desk.agent; the signed message is its borsh encoding
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SignedQuote {
+    pub desk: Pubkey,
+    pub consignment: Pubkey,
+    pub beneficiary: Pubkey,
+    pub token_amount: u64,
+    pub discount_bps: u16,
+    pub currency: u8,
+    pub lockup_secs: i64,
+    pub agent_commission_bps: u16,
+    pub expires_at: i64,
+    pub nonce: u64,
+}
+
+#[account]
+pub struct UsedQuote {
+    pub desk: Pubkey,
+    pub nonce: u64,
+    pub offer: Pubkey,
+}
+
+impl UsedQuote { pub const SIZE: usize = 32+8+32; }
+
+/// Buy-side RFQ with USDC escrowed in the desk treasury; sellers fill it via fill_bid with the desk co-signing
+#[account]
+pub struct Bid {
+    pub desk: Pubkey,
+    pub buyer: Pubkey,
+    pub token_mint: Pubkey,
+    pub max_token_amount: u64,
+    pub filled_amount: u64,
+    pub discount_bps: u16,
+    pub lockup_secs: i64,
+    pub escrow_remaining: u64, // USDC base units
+    pub created_at: i64,
+    pub expires_at: i64, // 0 = no expiry
+}
+
+impl Bid { pub const SIZE: usize = 32+32+32+8+8+2+8+8+8+8; }
+
+/// Sealed-bid auction over inventory reserved from a consignment
+#[account]
+pub struct Auction {
+    pub desk: Pubkey,
+    pub consignment: Pubkey,
+    pub token_amount: u64, // reserved from consignment.remaining_amount
+    pub max_discount_bps: u16, // consignment.max_discount_bps at creation, higher bids cannot be revealed
+    pub deposit_amount: u64, // minimum USDC base units per bid
+    pub commit_end_at: i64,
+    pub reveal_end_at: i64,
+    pub bid_count: u32,
+    pub revealed_count: u32,
+    pub allocated_amount: u64,
+    pub settled: bool,
+    pub deposit_bps: u16, // share of a revealed bid's discounted value its deposit must cover
+    pub claim_window_secs: i64,
+    pub claim_end_at: i64, // settlement time + claim_window_secs; unclaimed allocations return to the consignment after it
+    pub clearing_price_8d: u64, // consignment price locked at settlement, used for every winning offer
+    pub bids: Vec<RevealedBid>, // revealed bids in allocation order: lowest discount first, then commit order
+}
+
+impl Auction { pub const SIZE: usize = 32+32+8+2+8+8+8+4+4+8+1+2+8+8+8+4+MAX_AUCTION_BIDS*RevealedBid::SIZE; }
+
+pub const MAX_AUCTION_BIDS: usize = 32;
+
+/// Revealed bid as kept on its auction, so settlement needs no bid accounts
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RevealedBid {
+    pub bidder: Pubkey,
+    pub bid_index: u32,
+    pub discount_bps: u16,
+    pub token_amount: u64,
+    pub allocated_amount: u64, // set by settle_auction
+}
+
+impl RevealedBid { pub const SIZE: usize = 32+4+2+8+8; }
+
+#[account]
+pub struct SealedBid {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub commitment: [u8; 32], // keccak256(discount_bps le || token_amount le || salt || bidder)
+    pub deposit: u64,
+    pub bid_index: u32, // commit order, breaks discount ties
+    pub revealed: bool,
+    pub discount_bps: u16,
+    pub token_amount: u64,
+    pub allocated_amount: u64, // copied from the auction when the offer is claimed
+    pub offer_created: bool,
+    pub offer: Pubkey, // offer claimed by this bid, default = none
+}
+
+impl SealedBid { pub const SIZE: usize = 32+32+32+8+4+1+2+8+8+1+32; }
+
+fn validate_consignment_terms(
+    fixed_discount_bps: u16,
+    min_discount_bps: u16,
+    max_discount_bps: u16,
+    min_lockup_days: u32,
+    max_lockup_days: u32,
+    min_deal_amount: u64,
+    max_deal_amount: u64,
+) -> Result<()> {
+    require!(min_deal_amount <= max_deal_amount, OtcError::AmountRange);
+    require!(min_discount_bps <= max_discount_bps, OtcError::Discount);
+    require!(max_discount_bps <= 10000, OtcError::Discount); // Max 100% discount
+    require!(fixed_discount_bps <= 10000, OtcError::Discount); // Max 100% discount
+    require!(min_lockup_days <= max_lockup_days, OtcError::LockupTooLong);
+    Ok(())
+}
+
+/// A non-fractionalized consignment sells its whole remaining amount in one deal, so that amount
+/// must itself be a valid deal size
+fn check_block_deal_bounds(is_fractionalized: bool, amount: u64, min_deal_amount: u64, max_deal_amount: u64) -> Result<()> {
+    require!(is_fractionalized || (amount >= min_deal_amount && amount <= max_deal_amount), OtcError::AmountRange);
+    Ok(())
+}
+
+/// Discount band for an order: the matching tier's band, or the consignment's min/max band without tiers
+fn discount_band(consignment: &Consignment, token_amount: u64, price_8d: u64, decimals: u8) -> Result<(u16, u16)> {
+    if consignment.discount_tiers.is_empty() {
+        return Ok((consignment.min_discount_bps, consignment.max_discount_bps));
+    }
+    let size = match consignment.tier_basis {
+        TierBasis::TokenAmount => token_amount,
+        TierBasis::UsdNotional => calc_discounted_usd(token_amount, price_8d, decimals, 0)?,
+    };
+    let tier = consignment.discount_tiers.iter().rev().find(|t| t.min_size <= size).ok_or(OtcError::AmountRange)?;
+    Ok((tier.min_discount_bps, tier.max_discount_bps))
+}
+
+/// Discount and lockup must fall inside a negotiable consignment's band (or the order's tier)
+fn check_negotiated_band(consignment: &Consignment, token_amount: u64, price_8d: u64, decimals: u8, discount_bps: u16, lockup_secs: i64) -> Result<()> {
+    let (min_discount_bps, max_discount_bps) = discount_band(consignment, token_amount, price_8d, decimals)?;
+    require!(discount_bps >= min_discount_bps && discount_bps <= max_discount_bps, OtcError::Discount);
+    let lockup_days = lockup_secs / 86400;
+    require!(lockup_days >= consignment.min_lockup_days as i64 && lockup_days <= consignment.max_lockup_days as i64, OtcError::LockupTooLong);
+    check_discount_curve(&consignment.discount_curve, discount_bps, lockup_secs)
+}
+
+fn validate_discount_curve(points: &[DiscountCurvePoint]) -> Result<()> {
+    require!(points.len() <= MAX_DISCOUNT_CURVE_POINTS, OtcError::Discount);
+    require!(points.iter().all(|p| p.max_discount_bps <= 10000), OtcError::Discount);
+    require!(points.windows(2).all(|w| w[0].lockup_days < w[1].lockup_days), OtcError::Discount);
+    Ok(())
+}
+
+/// Discount may not exceed the curve's maximum for the lockup; an empty curve allows any discount
+fn check_discount_curve(curve: &[DiscountCurvePoint], discount_bps: u16, lockup_secs: i64) -> Result<()> {
+    if curve.is_empty() {
+        return Ok(());
+    }
+    let lockup_days = u16::try_from((lockup_secs / 86400).max(0)).unwrap_or(u16::MAX);
+    require!(discount_bps <= curve_max_discount_bps(curve, lockup_days), OtcError::DiscountAboveCurve);
+    Ok(())
+}
+
+/// Linear between points, flat past the ends, rounding the change from the lower point down
+fn curve_max_discount_bps(curve: &[DiscountCurvePoint], lockup_days: u16) -> u16 {
+    let (Some(first), Some(last)) = (curve.first(), curve.last()) else { return 0 };
+    if lockup_days <= first.lockup_days {
+        return first.max_discount_bps;
+    }
+    if lockup_days >= last.lockup_days {
+        return last.max_discount_bps;
+    }
+    let i = curve.iter().position(|p| p.lockup_days > lockup_days).unwrap_or(curve.len() - 1);
+    let (lo, hi) = (curve[i - 1], curve[i]);
+    let dx = (lockup_days - lo.lockup_days) as u32;
+    let span = (hi.lockup_days - lo.lockup_days) as u32;
+    if hi.max_discount_bps >= lo.max_discount_bps {
+        lo.max_discount_bps + (dx * (hi.max_discount_bps - lo.max_discount_bps) as u32 / span) as u16
+    } else {
+        lo.max_discount_bps - (dx * (lo.max_discount_bps - hi.max_discount_bps) as u32 / span) as u16
+    }
+}
+
+/// Commission for negotiated terms: from the desk policy when set, otherwise the requested 25-150 bps
+fn negotiated_commission_bps(desk: &Desk, policy: Option<&CommissionPolicy>, requested_bps: u16, discount_bps: u16, lockup_secs: i64) -> Result<u16> {
+    match policy {
+        Some(policy) => policy.commission_bps(requested_bps, discount_bps, (lockup_secs / 86400) as u64),
+        None => {
+            require!(!desk.commission_policy_enabled, OtcError::CommissionPolicyRequired);
+            // Negotiated deals: commission must be 25-150 bps (0.25% - 1.5%)
+            require!(requested_bps >= 25 && requested_bps <= 150, OtcError::CommissionRange);
+            Ok(requested_bps)
+        }
+    }
+}
+
+fn is_dutch_auction(consignment: &Consignment) -> bool {
+    consignment.auction_end_at > 0
+}
+
+/// Discount in effect at `now`: min_discount_bps before the window, max_discount_bps after it
+fn dutch_discount_bps(consignment: &Consignment, now: i64) -> u16 {
+    let (start, end) = (consignment.auction_start_at, consignment.auction_end_at);
+    let (from, to) = (consignment.min_discount_bps, consignment.max_discount_bps);
+    if now <= start {
+        return from;
+    }
+    if now >= end {
+        return to;
+    }
+    let elapsed = (now - start) as u128;
+    let window = (end - start) as u128;
+    from + ((to - from) as u128 * elapsed / window) as u16
+}
+
+/// Whether a P2P buyer's discount is assigned at order time rather than fixed
+fn has_dynamic_p2p_discount(consignment: &Consignment) -> bool {
+    is_dutch_auction(consignment) || !consignment.discount_tiers.is_empty()
+}
+
+fn accepts_currency(consignment: &Consignment, currency: u8) -> bool {
+    currency < 8 && consignment.accepted_currencies & (1 << currency) != 0
+}
+
+/// Return an unsold offer's tokens to its consignment, re-listing it unless the consigner withdrew
+fn restore_consignment_inventory(consignment: &mut Consignment, amount: u64) -> Result<()> {
+    consignment.remaining_amount = consignment.remaining_amount.checked_add(amount).ok_or(OtcError::Overflow)?;
+    if !consignment.is_withdrawn {
+        consignment.is_active = true;
+    }
+    Ok(())
+}
+
+/// Amount settle_auction allocated to a bid, 0 if it was not revealed or won nothing
+fn auction_allocation(auction: &Auction, bid_index: u32) -> u64 {
+    auction.bids.iter().find(|b| b.bid_index == bid_index).map_or(0, |b| b.allocated_amount)
+}
+
+/// USDC base units (rounded up) a bid of usd_8d must escrow at deposit_bps
+fn auction_deposit_required(usd_8d: u64, deposit_bps: u16, usdc_decimals: u8) -> Result<u64> {
+    let scaled = (usd_8d as u128).checked_mul(deposit_bps as u128).ok_or(OtcError::Overflow)?;
+    safe_u128_to_u64(mul_div_ceil_u128(scaled, pow10(usdc_decimals as u32), 100_000_000 * 10_000)?)
+}
+
+/// Price an offer's reserve is checked against at fulfillment: the consignment price now, so a price move
+/// after the offer was created cannot take it under the reserve; offers without a reserve skip the lookup
+fn reserve_check_price_8d(
+    desk: &Desk,
+    offer: &Offer,
+    consignment: Option<&Consignment>,
+    registry: Option<&TokenRegistry>,
+    now: i64,
+) -> Result<u64> {
+    if offer.min_net_price_usd_8d == 0 {
+        return Ok(offer.price_usd_per_token_8d);
+    }
+    let (Some(consignment), Some(registry)) = (consignment, registry) else { return err!(OtcError::BadState) };
+    consignment_price_8d(desk, consignment, registry, now)
+}
+
+fn is_consignment_expired(consignment: &Consignment, now: i64) -> bool {
+    consignment.expires_at > 0 && now >= consignment.expires_at
+}
+
+/// Sorted-pair keccak256 merkle proof with leaf = keccak256(pubkey)
+fn verify_allowlist_proof(root: &[u8; 32], who: &Pubkey, proof: &[[u8; 32]]) -> bool {
+    let mut node = keccak::hash(who.as_ref()).to_bytes();
+    for sibling in proof {
+        node = if node <= *sibling {
+            keccak::hashv(&[&node, sibling]).to_bytes()
+        } else {
+            keccak::hashv(&[sibling, &node]).to_bytes()
+        };
+    }
+    node == *root
+}
+
+/// Attestation stored at a wallet's attestation PDA, None when the wallet was never attested
+/// The caller derives the address, so an empty account proves there is no attestation
+fn read_attestation(info: &AccountInfo) -> Result<Option<Attestation>> {
+    if info.data_is_empty() {
+        return Ok(None);
+    }
+    require!(info.owner == &crate::ID, OtcError::BadState);
+    let data = info.try_borrow_data()?;
+    Ok(Some(Attestation::try_deserialize(&mut &data[..])?))
+}
+
+/// Denylisted wallets are always rejected;
+/// gated instructions additionally require a live attestation within its tier's USD cap
+fn check_compliance(
+    desk: &Desk,
+    flag: u8,
+    config: Option<&ComplianceConfig>,
+    attestation: &AccountInfo,
+    usd_8d: u64,
+    now: i64,
+) -> Result<()> {
+    let attestation = read_attestation(attestation)?;
+    if let Some(att) = &attestation {
+        require!(!att.denied, OtcError::WalletDenied);
+    }
+    if desk.compliance_required_mask & flag == 0 {
+        return Ok(());
+    }
+    let config = config.ok_or(OtcError::AttestationRequired)?;
+    let att = attestation.ok_or(OtcError::AttestationRequired)?;
+    require!(att.expires_at == 0 || now < att.expires_at, OtcError::AttestationExpired);
+    let cap = config.tier_max_usd_8d[att.tier as usize];
+    require!(cap == 0 || usd_8d <= cap, OtcError::TierLimitExceeded);
+    Ok(())
+}
+
+/// Credit a fee share to its recipient's accrual; the funds stay in the desk treasury until claimed
+fn accrue_fee(
+    desk: &mut Desk,
+    desk_key: Pubkey,
+    accrual: &mut CommissionAccrual,
+    recipient: Pubkey,
+    kind: FeeKind,
+    offer: Pubkey,
+    currency: u8,
+    amount: u64,
+) -> Result<()> {
+    if accrual.recipient == Pubkey::default() {
+        accrual.desk = desk_key;
+        accrual.recipient = recipient;
+        accrual.currency = currency;
+    }
+    require!(accrual.desk == desk_key && accrual.recipient == recipient && accrual.currency == currency, OtcError::BadState);
+    if amount == 0 {
+        return Ok(());
+    }
+    accrual.accrued_amount = accrual.accrued_amount.checked_add(amount).ok_or(OtcError::Overflow)?;
+    if currency == 1 {
+        desk.commission_owed_usdc = desk.commission_owed_usdc.checked_add(amount).ok_or(OtcError::Overflow)?;
+    } else {
+        desk.commission_owed_lamports = desk.commission_owed_lamports.checked_add(amount).ok_or(OtcError::Overflow)?;
+    }
+    emit!(FeeAccrued { offer, recipient, kind, amount, currency, accrued_amount: accrual.accrued_amount });
+    Ok(())
+}
+
+/// Route every fee share of a settlement to its accrual; protocol and referrer accruals are
+/// only required when their share is non-zero
+fn accrue_settlement_fees(
+    desk: &mut Desk,
+    desk_key: Pubkey,
+    offer: &mut Offer,
+    offer_key: Pubkey,
+    settlement: &Settlement,
+    agent_accrual: &mut CommissionAccrual,
+    protocol_accrual: Option<&mut CommissionAccrual>,
+    referrer_accrual: Option<&mut CommissionAccrual>,
+) -> Result<()> {
+    let currency = settlement.currency;
+    let (agent, protocol_treasury) = (desk.agent, desk.protocol_treasury);
+    accrue_fee(desk, desk_key, agent_accrual, agent, FeeKind::Agent, offer_key, currency, settlement.commission)?;
+    if settlement.protocol_fee > 0 {
+        let accrual = protocol_accrual.ok_or(OtcError::FeeAccountRequired)?;
+        accrue_fee(desk, desk_key, accrual, protocol_treasury, FeeKind::Protocol, offer_key, currency, settlement.protocol_fee)?;
+    }
+    if settlement.referrer_fee > 0 {
+        let accrual = referrer_accrual.ok_or(OtcError::FeeAccountRequired)?;
+        accrue_fee(desk, desk_key, accrual, offer.referrer, FeeKind::Referrer, offer_key, currency, settlement.referrer_fee)?;
+    }
+    offer.commission_recipient = agent;
+    offer.commission_accrued = settlement.commission;
+    offer.protocol_fee_recipient = protocol_treasury;
+    offer.protocol_fee_accrued = settlement.protocol_fee;
+    offer.referrer_fee_accrued = settlement.referrer_fee;
+    Ok(())
+}
+
+/// Take back a fee share credited for an offer that is being refunded
+/// Whatever the recipient already claimed stays paid and the desk covers it; returns the amount reversed
+fn reverse_fee(
+    desk: &mut Desk,
+    desk_key: Pubkey,
+    accrual: Option<&mut CommissionAccrual>,
+    recipient: Pubkey,
+    kind: FeeKind,
+    offer: Pubkey,
+    currency: u8,
+    amount: u64,
+) -> Result<u64> {
+    if amount == 0 {
+        return Ok(0);
+    }
+    let accrual = accrual.ok_or(OtcError::FeeAccountRequired)?;
+    require!(accrual.desk == desk_key && accrual.recipient == recipient && accrual.currency == currency, OtcError::BadState);
+    let unclaimed = accrual.accrued_amount.checked_sub(accrual.claimed_amount).ok_or(OtcError::Overflow)?;
+    let reversed = amount.min(unclaimed);
+    accrual.accrued_amount = accrual.accrued_amount.checked_sub(reversed).ok_or(OtcError::Overflow)?;
+    if currency == 1 {
+        desk.commission_owed_usdc = desk.commission_owed_usdc.checked_sub(reversed).ok_or(OtcError::Overflow)?;
+    } else {
+        desk.commission_owed_lamports = desk.commission_owed_lamports.checked_sub(reversed).ok_or(OtcError::Overflow)?;
+    }
+    emit!(FeeReversed { offer, recipient, kind, amount: reversed, currency, accrued_amount: accrual.accrued_amount });
+    Ok(reversed)
+}
+
+/// Reverse every fee share credited when the offer was paid; accruals are only required for non-zero shares
+fn reverse_settlement_fees(
+    desk: &mut Desk,
+    desk_key: Pubkey,
+    offer: &mut Offer,
+    offer_key: Pubkey,
+    agent_accrual: Option<&mut CommissionAccrual>,
+    protocol_accrual: Option<&mut CommissionAccrual>,
+    referrer_accrual: Option<&mut CommissionAccrual>,
+) -> Result<()> {
+    let currency = offer.currency;
+    reverse_fee(desk, desk_key, agent_accrual, offer.commission_recipient, FeeKind::Agent, offer_key, currency, offer.commission_accrued)?;
+    reverse_fee(desk, desk_key, protocol_accrual, offer.protocol_fee_recipient, FeeKind::Protocol, offer_key, currency, offer.protocol_fee_accrued)?;
+    reverse_fee(desk, desk_key, referrer_accrual, offer.referrer, FeeKind::Referrer, offer_key, currency, offer.referrer_fee_accrued)?;
+    offer.commission_accrued = 0;
+    offer.protocol_fee_accrued = 0;
+    offer.referrer_fee_accrued = 0;
+    Ok(())
+}
+
+/// Snapshot the desk fee schedule and optional referrer onto a new offer
+fn apply_fee_schedule(desk: &Desk, offer: &mut Offer, referrer: Option<Pubkey>) -> Result<()> {
+    offer.protocol_fee_bps = desk.protocol_fee_bps;
+    match referrer {
+        Some(referrer) => {
+            require!(referrer != offer.beneficiary, OtcError::BadState);
+            offer.referrer = referrer;
+            offer.referrer_bps = desk.referrer_bps;
+        }
+        None => {
+            offer.referrer = Pubkey::default();
+            offer.referrer_bps = 0;
+        }
+    }
+    Ok(())
+}
+
+/// Enforce is_private: only consigner, owner, agent, approvers, or allowlisted beneficiaries can create offers
+fn check_private_access(desk: &Desk, consignment: &Consignment, caller: &Pubkey, allowlist_proof: &[[u8; 32]]) -> Result<()> {
+    if !consignment.is_private {
+        return Ok(());
+    }
+    let privileged = *caller == consignment.consigner || 
+        *caller == desk.owner || 
+        *caller == desk.agent || 
+        desk.approvers.contains(caller);
+    if !privileged {
+        require!(consignment.allowlist_root != [0u8; 32], OtcError::FulfillRestricted);
+        require!(verify_allowlist_proof(&consignment.allowlist_root, caller, allowlist_proof), OtcError::NotAllowlisted);
+    }
+    Ok(())
+}
+
+impl SettlementQuote {
+    fn new(settlement: &Settlement, unlock_time: i64) -> Self {
+        SettlementQuote {
+            gross_usd_8d: settlement.gross_usd_8d,
+            discounted_usd_8d: settlement.discounted_usd_8d,
+            currency: settlement.currency,
+            payment_amount: settlement.payer_debit,
+            commission_amount: settlement.commission,
+            protocol_fee_amount: settlement.protocol_fee,
+            referrer_fee_amount: settlement.referrer_fee,
+            consigner_net_amount: settlement.seller_net,
+            unlock_time,
+        }
+    }
+}
+
+/// Per-token USD price for a consignment order: the consigner's fixed price, or the registry price
+/// (which must be set and fresh)
+fn consignment_price_8d(desk: &Desk, consignment: &Consignment, registry: &TokenRegistry, now: i64) -> Result<u64> {
+    require!(registry.token_mint == consignment.token_mint, OtcError::BadState); // Ensure registry matches consignment
+    if consignment.fixed_price_usd_8d > 0 {
+        return Ok(consignment.fixed_price_usd_8d);
+    }
+    require!(registry.token_usd_price_8d > 0, OtcError::NoPrice);
+    if registry.prices_updated_at > 0 {
+        require!(now - registry.prices_updated_at <= desk.max_price_age_secs, OtcError::StalePrice);
+    }
+    Ok(registry.token_usd_price_8d)
+}
+
+/// Order terms requested against a consignment
+#[derive(Clone, Copy)]
+struct ConsignmentOrder {
+    token_amount: u64,
+    discount_bps: u16,
+    currency: u8,
+    lockup_secs: i64,
+    agent_commission_bps: u16,
+    referrer: Option<Pubkey>,
+}
+
+/// Consignment order priced on the consignment's terms, before any inventory is reserved
+struct PricedOrder {
+    price_8d: u64,
+    discount_bps: u16, // assigned discount for Dutch auctions and P2P tiers
+    commission_bps: u16,
+    total_usd_disc: u64,
+}
+
+/// Currency, state and size checks for any sale out of a consignment
+fn check_consignment_sale(consignment: &Consignment, currency: u8, token_amount: u64, now: i64) -> Result<()> {
+    require!(accepts_currency(consignment, currency), OtcError::CurrencyNotAccepted);
+    require!(consignment.is_active, OtcError::BadState);
+    require!(!is_consignment_expired(consignment, now), OtcError::ConsignmentExpired);
+
+    require!(token_amount >= consignment.min_deal_amount && token_amount <= consignment.max_deal_amount, OtcError::AmountRange);
+    require!(token_amount <= consignment.remaining_amount, OtcError::InsuffInv);
+    // Non-fractionalized consignments sell as a single block
+    if !consignment.is_fractionalized {
+        require!(token_amount == consignment.remaining_amount, OtcError::NotFractionalized);
+    }
+    Ok(())
+}
+
+/// Consignment state checks for re-terming an offer; taking more than the offer already holds needs a listed consignment
+fn check_counter_consignment(consignment: &Consignment, offer: &Offer, token_amount: u64, now: i64) -> Result<()> {
+    require!(accepts_currency(consignment, offer.currency), OtcError::CurrencyNotAccepted);
+    require!(!consignment.is_withdrawn, OtcError::BadState);
+    require!(!consignment.auction_live, OtcError::AuctionPhase);
+    require!(!is_consignment_expired(consignment, now), OtcError::ConsignmentExpired);
+    if token_amount > offer.token_amount {
+        require!(consignment.is_active, OtcError::BadState);
+    }
+    Ok(())
+}
+
+/// Validate an order against the consignment terms and price it at the current consignment price
+/// Shared by open_consignment_offer and quote_order so a quote matches the offer it would open
+fn price_consignment_order(
+    desk: &Desk,
+    consignment: &Consignment,
+    registry: &TokenRegistry,
+    policy: Option<&CommissionPolicy>,
+    order: &ConsignmentOrder,
+    now: i64,
+) -> Result<PricedOrder> {
+    let ConsignmentOrder { token_amount, discount_bps, currency, lockup_secs, agent_commission_bps, .. } = *order;
+    require!(!desk.paused, OtcError::Paused);
+    require!(currency == 0 || currency == 1, OtcError::UnsupportedCurrency);
+    check_consignment_sale(consignment, currency, token_amount, now)?;
+
+    let price_8d = consignment_price_8d(desk, consignment, registry, now)?;
+
+    // Dutch auctions and tiered P2P consignments: discount_bps is the least the buyer accepts;
+    // the offer gets the discount in effect now / for the order's tier
+    let discount_bps = if !consignment.is_negotiable && has_dynamic_p2p_discount(consignment) {
+        let assigned_bps = if is_dutch_auction(consignment) {
+            dutch_discount_bps(consignment, now)
+        } else {
+            discount_band(consignment, token_amount, price_8d, registry.decimals)?.0
+        };
+        require!(discount_bps <= assigned_bps, OtcError::Discount);
+        assigned_bps
+    } else {
+        discount_bps
+    };
+
+    // Determine effective commission for the offer
+    let commission_bps: u16 = if consignment.is_negotiable {
+        check_negotiated_band(consignment, token_amount, price_8d, registry.decimals, discount_bps, lockup_secs)?;
+        negotiated_commission_bps(desk, policy, agent_commission_bps, discount_bps, lockup_secs)?
+    } else {
+        require!(has_dynamic_p2p_discount(consignment) || discount_bps == consignment.fixed_discount_bps, OtcError::Discount);
+        let lockup_days = lockup_secs / 86400;
+        require!(lockup_days == consignment.fixed_lockup_days as i64, OtcError::LockupTooLong);
+        check_discount_curve(&consignment.discount_curve, discount_bps, lockup_secs)?;
+        // P2P deals: use the configured p2p_commission_bps (default 0.25%)
+        // agent_commission_bps parameter is ignored for P2P - uses desk-wide setting
+        desk.p2p_commission_bps
+    };
+
+    // Check implied USD value meets minimum
+    let total_usd_disc = calc_discounted_usd(token_amount, price_8d, registry.decimals, discount_bps)?;
+    require!(total_usd_disc >= desk.min_usd_amount_8d, OtcError::MinUsd);
+    Ok(PricedOrder { price_8d, discount_bps, commission_bps, total_usd_disc })
+}
+
+/// Validate an order against the consignment terms, reserve inventory and write an unapproved offer
+/// Returns the discounted USD value of the order
+fn open_consignment_offer(
+    desk: &mut Desk,
+    desk_key: Pubkey,
+    consignment: &mut Consignment,
+    registry: &TokenRegistry,
+    policy: Option<&CommissionPolicy>,
+    offer: &mut Offer,
+    offer_key: Pubkey,
+    beneficiary: Pubkey,
+    rent_payer: Pubkey,
+    order: ConsignmentOrder,
+    now: i64,
+) -> Result<u64> {
+    let PricedOrder { price_8d, discount_bps, commission_bps, total_usd_disc } =
+        price_consignment_order(desk, consignment, registry, policy, &order, now)?;
+    let ConsignmentOrder { token_amount, currency, lockup_secs, referrer, .. } = order;
+
+    consignment.remaining_amount = consignment.remaining_amount.checked_sub(token_amount).ok_or(OtcError::Overflow)?;
+    if consignment.remaining_amount == 0 {
+        consignment.is_active = false;
+    }
+
+    write_consignment_offer(
+        desk,
+        desk_key,
+        consignment,
+        registry,
+        price_8d,
+        offer,
+        offer_key,
+        beneficiary,
+        rent_payer,
+        ConsignmentOrder { token_amount, discount_bps, currency, lockup_secs, agent_commission_bps: commission_bps, referrer },
+        now,
+    )?;
+    Ok(total_usd_disc)
+}
+
+/// Assign the next offer id and write an unapproved offer for consignment inventory already reserved
+/// at the registry's current price; order.agent_commission_bps is the effective commission
+fn write_consignment_offer(
+    desk: &mut Desk,
+    desk_key: Pubkey,
+    consignment: &Consignment,
+    registry: &TokenRegistry,
+    price_8d: u64,
+    offer: &mut Offer,
+    offer_key: Pubkey,
+    beneficiary: Pubkey,
+    rent_payer: Pubkey,
+    order: ConsignmentOrder,
+    now: i64,
+) -> Result<()> {
+    let ConsignmentOrder { token_amount, discount_bps, currency, lockup_secs, agent_commission_bps, referrer } = order;
+    check_reserve_price(consignment.min_net_price_usd_8d, price_8d, discount_bps)?;
+    let offer_id = desk.next_offer_id;
+    desk.next_offer_id = offer_id.checked_add(1).ok_or(OtcError::Overflow)?;
+
+    offer.desk = desk_key;
+    offer.consignment_id = consignment.id;
+    offer.token_mint = consignment.token_mint;
+    offer.token_decimals = registry.decimals;
+    offer.id = offer_id;
+    offer.beneficiary = beneficiary;
+    offer.token_amount = token_amount;
+    offer.discount_bps = discount_bps;
+    offer.created_at = now;
+    offer.unlock_time = now.checked_add(lockup_secs).ok_or(OtcError::Overflow)?;
+    offer.price_usd_per_token_8d = price_8d;
+    offer.max_price_deviation_bps = consignment.max_price_volatility_bps;
+    offer.sol_usd_price_8d = if currency == 0 { desk.sol_usd_price_8d } else { 0 };
+    offer.currency = currency;
+    offer.approved = false;
+    offer.paid = false;
+    offer.fulfilled = false;
+    offer.cancelled = false;
+    offer.payer = Pubkey::default();
+    offer.amount_paid = 0;
+    offer.agent_commission_bps = agent_commission_bps;
+    offer.quote_expiry_secs = consignment.max_time_to_execute_secs;
+    offer.rent_payer = rent_payer;
+    offer.min_net_price_usd_8d = consignment.min_net_price_usd_8d;
+    apply_fee_schedule(desk, offer, referrer)?;
+
+    emit!(OfferCreated {
+        desk: desk_key,
+        offer: offer_key,
+        beneficiary,
+        token_amount,
+        discount_bps,
+        currency
+    });
+    Ok(())
 }
 
-impl Offer { pub const SIZE: usize = 32+8+32+1+8+32+8+2+8+8+8+2+8+1+1+1+1+1+32+8+2; } // +2 for agent_commission_bps
+/// Check that the instruction right before this one is an Ed25519 sig-verify of `message` by `signer`
+/// with signature, key and message all carried inline in that instruction
+fn verify_ed25519_signature(ix_sysvar: &AccountInfo, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    let current = sysvar_instructions::load_current_index_checked(ix_sysvar)?;
+    require!(current > 0, OtcError::InvalidSignature);
+    let ix = sysvar_instructions::load_instruction_at_checked((current - 1) as usize, ix_sysvar)?;
+    require!(ix.program_id == ed25519_program::ID && ix.accounts.is_empty(), OtcError::InvalidSignature);
+
+    // Layout: [num_signatures u8, padding u8, 7 x u16 offsets, ...data]
+    let data = &ix.data;
+    require!(data.len() >= 16 && data[0] == 1, OtcError::InvalidSignature);
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let signature_offset = read_u16(2) as usize;
+    let signature_ix = read_u16(4);
+    let pubkey_offset = read_u16(6) as usize;
+    let pubkey_ix = read_u16(8);
+    let message_offset = read_u16(10) as usize;
+    let message_size = read_u16(12) as usize;
+    let message_ix = read_u16(14);
+    require!(signature_ix == u16::MAX && pubkey_ix == u16::MAX && message_ix == u16::MAX, OtcError::InvalidSignature);
+
+    require!(data.len() >= signature_offset.saturating_add(64), OtcError::InvalidSignature);
+    let pubkey = data.get(pubkey_offset..pubkey_offset.saturating_add(32)).ok_or(OtcError::InvalidSignature)?;
+    require!(pubkey == signer.as_ref(), OtcError::InvalidSignature);
+    let signed = data.get(message_offset..message_offset.saturating_add(message_size)).ok_or(OtcError::InvalidSignature)?;
+    require!(signed == message, OtcError::InvalidSignature);
+    Ok(())
+}
 
 fn only_owner(desk: &Desk, who: &Pubkey) -> Result<()> { require!(*who == desk.owner, OtcError::NotOwner); Ok(()) }
 fn must_be_approver(desk: &Desk, who: &Pubkey) -> Result<()> { require!((*who == desk.agent) || desk.approvers.contains(who), OtcError::NotApprover); Ok(()) }
-fn pow10(exp: u32) -> u128 { 10u128.pow(exp) }
-fn mul_div_u128(a: u128, b: u128, d: u128) -> Result<u128> { a.checked_mul(b).and_then(|x| x.checked_div(d)).ok_or(OtcError::Overflow.into()) }
-fn mul_div_ceil_u128(a: u128, b: u128, d: u128) -> Result<u128> { let prod = a.checked_mul(b).ok_or(OtcError::Overflow)?; let q = prod / d; let r = prod % d; Ok(if r == 0 { q } else { q + 1 }) }
-fn safe_u128_to_u64(value: u128) -> Result<u64> { u64::try_from(value).map_err(|_| OtcError::Overflow.into()) }
+/// Offers from a consignment with an execution window expire on that window instead of the desk default
+fn offer_expiry(desk: &Desk, offer: &Offer) -> Result<i64> {
+    let window = if offer.quote_expiry_secs > 0 { offer.quote_expiry_secs } else { desk.quote_expiry_secs };
+    offer.created_at.checked_add(window).ok_or(OtcError::Overflow.into())
+}
 
 fn check_price_deviation(old_price: u64, new_price: u64, max_deviation_bps: u16) -> Result<()> {
     if old_price == 0 || max_deviation_bps == 0 {
@@ -1694,15 +4790,6 @@ fn check_price_deviation(old_price: u64, new_price: u64, max_deviation_bps: u16)
     Ok(())
 }
 
-fn calc_discounted_usd(token_amount: u64, price_8d: u64, decimals: u8, discount_bps: u16) -> Result<u64> {
-    let token_dec = decimals as u32;
-    let usd_8d = safe_u128_to_u64(mul_div_u128(token_amount as u128, price_8d as u128, pow10(token_dec) as u128)?)?;
-    usd_8d.checked_mul((10_000 - discount_bps as u64) as u64)
-        .ok_or(OtcError::Overflow)?
-        .checked_div(10_000)
-        .ok_or(OtcError::Overflow.into())
-}
-
 // AMM Program IDs (mainnet)
 const RAYDIUM_AMM_V4: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
 const RAYDIUM_CPMM: &str = "CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C";
@@ -1716,20 +4803,31 @@ fn is_raydium_program(program_id: &Pubkey) -> bool {
 fn is_orca_program(program_id: &Pubkey) -> bool { program_id.to_string() == ORCA_WHIRLPOOL }
 fn is_pumpswap_program(program_id: &Pubkey) -> bool { program_id.to_string() == PUMPSWAP_PROGRAM }
 
-fn convert_pyth_price(price: i64, exponent: i32) -> Result<u64> {
-    require!(price > 0, OtcError::BadPrice);
-    let exp_diff = 8i32.checked_sub(exponent).ok_or(OtcError::Overflow)?;
-    require!(exp_diff <= 38 && exp_diff >= -38, OtcError::BadPrice);
-    #[allow(clippy::cast_sign_loss)]
-    let price_u128 = price as u128;
-    let result = if exp_diff >= 0 {
-        #[allow(clippy::cast_sign_loss)]
-        price_u128.checked_mul(10u128.pow(exp_diff as u32)).ok_or(OtcError::Overflow)?
-    } else {
-        #[allow(clippy::cast_sign_loss)]
-        price_u128.checked_div(10u128.pow((-exp_diff) as u32)).ok_or(OtcError::Overflow)?
+/// Read a fresh Pyth price for feed_id in 8 decimals, rejecting updates below min_verification
+/// and a confidence wider than max_conf_bps of the price
+/// upper_edge picks price + conf (otherwise price - conf) in Conservative mode
+fn pyth_price_8d(
+    price_update: &PriceUpdateV2,
+    clock: &Clock,
+    max_age: u64,
+    feed_id: &[u8; 32],
+    min_verification: PythVerification,
+    max_conf_bps: u16,
+    mode: PythPriceMode,
+    upper_edge: bool,
+) -> Result<u64> {
+    require!(price_update.verification_level.gte(min_verification.level()), OtcError::InsufficientVerification);
+    let spot = price_update
+        .get_price_no_older_than_with_custom_verification_level(clock, max_age, feed_id, min_verification.level())
+        .map_err(|_| OtcError::StalePrice)?;
+    let quote = PythQuote {
+        price: spot.price,
+        conf: spot.conf,
+        ema_price: price_update.price_message.ema_price,
+        ema_conf: price_update.price_message.ema_conf,
+        exponent: spot.exponent,
     };
-    u64::try_from(result).map_err(|_| OtcError::Overflow.into())
+    select_price_8d(&quote, max_conf_bps, mode, upper_edge)
 }
 
 #[error_code]
@@ -1765,6 +4863,33 @@ pub enum OtcError {
     #[msg("Price update too frequent")] UpdateTooFrequent,
     #[msg("Commission must be 0 for P2P or 25-150 bps for negotiated")] CommissionRange,
     #[msg("Non-negotiable offers are P2P (auto-approved)")] NonNegotiableP2P,
+    #[msg("Consignment is not fractionalized; offer must take the full remaining amount")] NotFractionalized,
+    #[msg("Consignment expired")] ConsignmentExpired,
+    #[msg("Beneficiary not on consignment allowlist")] NotAllowlisted,
+    #[msg("Compliance attestation required")] AttestationRequired,
+    #[msg("Compliance attestation expired")] AttestationExpired,
+    #[msg("Wallet is denylisted")] WalletDenied,
+    #[msg("Offer exceeds compliance tier limit")] TierLimitExceeded,
+    #[msg("Missing or invalid Ed25519 quote signature")] InvalidSignature,
+    #[msg("Consignment is negotiable")] NegotiableConsignment,
+    #[msg("Payment exceeds buyer maximum")] MaxPaymentExceeded,
+    #[msg("Nothing to claim")] NothingToClaim,
+    #[msg("Desk commission policy account required")] CommissionPolicyRequired,
+    #[msg("Fee accrual account required for a non-zero fee share")] FeeAccountRequired,
+    #[msg("Not in the required auction phase")] AuctionPhase,
+    #[msg("Revealed bid does not match commitment")] InvalidReveal,
+    #[msg("Offer has a pending counter")] CounterPending,
+    #[msg("Amount is reserved for accrued fees or escrow")] CommissionOwed,
+    #[msg("Discount exceeds the maximum for this lockup")] DiscountAboveCurve,
+    #[msg("Discounted price is below the consignment reserve")] BelowReservePrice,
+    #[msg("Consignment does not accept this currency")] CurrencyNotAccepted,
+    #[msg("Oracle confidence interval too wide")] ConfidenceTooWide,
+    #[msg("Price update verification level too low")] InsufficientVerification,
+    #[msg("Execution window must be 0 or at least 60 seconds")] ExecuteWindow,
+    #[msg("Auction bid limit reached")] AuctionFull,
+    #[msg("Bid deposit too low")] DepositTooLow,
+    #[msg("Auction bid deposit must be released first")] DepositPending,
+    #[msg("Offer cannot be emergency refunded")] NotRefundable,
 }
 
 
@@ -0,0 +1,114 @@
+//! Pyth price selection shared by the desk and token registry oracle updates.
+//!
+//! Spot and Conservative read the latest aggregate price, Ema the moving average; the confidence
+//! limit is checked against the interval of the price actually used. Conservative then moves to the
+//! edge of the band that favours the desk.
+
+use anchor_lang::prelude::*;
+
+use crate::{OtcError, PythPriceMode};
+
+/// Prices and confidences read from a verified, fresh Pyth update, all in the feed's exponent
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct PythQuote {
+    pub(crate) price: i64,
+    pub(crate) conf: u64,
+    pub(crate) ema_price: i64,
+    pub(crate) ema_conf: u64,
+    pub(crate) exponent: i32,
+}
+
+/// Pick the price for mode, reject a confidence wider than max_conf_bps of it (0 = no limit) and
+/// convert to 8 decimals; upper_edge picks price + conf (otherwise price - conf) in Conservative mode
+pub(crate) fn select_price_8d(quote: &PythQuote, max_conf_bps: u16, mode: PythPriceMode, upper_edge: bool) -> Result<u64> {
+    let (price, conf) = match mode {
+        PythPriceMode::Ema => (quote.ema_price, quote.ema_conf),
+        PythPriceMode::Spot | PythPriceMode::Conservative => (quote.price, quote.conf),
+    };
+    require!(price > 0, OtcError::BadPrice);
+    if max_conf_bps > 0 {
+        #[allow(clippy::cast_sign_loss)]
+        let limit = price as u128 * max_conf_bps as u128;
+        require!(conf as u128 * 10_000 <= limit, OtcError::ConfidenceTooWide);
+    }
+    let price = if mode == PythPriceMode::Conservative {
+        let conf = i64::try_from(conf).map_err(|_| OtcError::Overflow)?;
+        if upper_edge { price.checked_add(conf) } else { price.checked_sub(conf) }.ok_or(OtcError::Overflow)?
+    } else {
+        price
+    };
+    convert_pyth_price(price, quote.exponent)
+}
+
+/// Pyth values are price * 10^exponent, so 8 decimals is price * 10^(exponent + 8)
+pub(crate) fn convert_pyth_price(price: i64, exponent: i32) -> Result<u64> {
+    require!(price > 0, OtcError::BadPrice);
+    let exp_diff = exponent.checked_add(8).ok_or(OtcError::Overflow)?;
+    require!(exp_diff <= 38 && exp_diff >= -38, OtcError::BadPrice);
+    #[allow(clippy::cast_sign_loss)]
+    let price_u128 = price as u128;
+    let result = if exp_diff >= 0 {
+        #[allow(clippy::cast_sign_loss)]
+        price_u128.checked_mul(10u128.pow(exp_diff as u32)).ok_or(OtcError::Overflow)?
+    } else {
+        #[allow(clippy::cast_sign_loss)]
+        price_u128.checked_div(10u128.pow((-exp_diff) as u32)).ok_or(OtcError::Overflow)?
+    };
+    u64::try_from(result).map_err(|_| OtcError::Overflow.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // $100.00 spot +/- $0.50, EMA $98.00 +/- $2.00, exponent -8
+    fn quote() -> PythQuote {
+        PythQuote { price: 100_0000_0000, conf: 5000_0000, ema_price: 98_0000_0000, ema_conf: 2_0000_0000, exponent: -8 }
+    }
+
+    fn err_of(r: Result<u64>) -> anchor_lang::error::Error {
+        r.expect_err("expected an error")
+    }
+
+    #[test]
+    fn spot_and_ema_pick_their_own_price() {
+        assert_eq!(select_price_8d(&quote(), 0, PythPriceMode::Spot, true).unwrap(), 100_0000_0000);
+        assert_eq!(select_price_8d(&quote(), 0, PythPriceMode::Ema, true).unwrap(), 98_0000_0000);
+    }
+
+    #[test]
+    fn conservative_takes_the_band_edge() {
+        assert_eq!(select_price_8d(&quote(), 0, PythPriceMode::Conservative, true).unwrap(), 100_5000_0000);
+        assert_eq!(select_price_8d(&quote(), 0, PythPriceMode::Conservative, false).unwrap(), 99_5000_0000);
+    }
+
+    #[test]
+    fn confidence_limit_uses_the_selected_interval() {
+        // Spot conf is 0.5% of the price, EMA conf about 2.04%
+        assert!(select_price_8d(&quote(), 50, PythPriceMode::Spot, true).is_ok());
+        assert_eq!(err_of(select_price_8d(&quote(), 49, PythPriceMode::Spot, true)), OtcError::ConfidenceTooWide.into());
+        assert!(select_price_8d(&quote(), 205, PythPriceMode::Ema, true).is_ok());
+        assert_eq!(err_of(select_price_8d(&quote(), 200, PythPriceMode::Ema, true)), OtcError::ConfidenceTooWide.into());
+        // No limit
+        let wide = PythQuote { conf: 90_0000_0000, ..quote() };
+        assert!(select_price_8d(&wide, 0, PythPriceMode::Spot, true).is_ok());
+    }
+
+    #[test]
+    fn rejects_non_positive_prices() {
+        let negative = PythQuote { price: -1, ..quote() };
+        assert_eq!(err_of(select_price_8d(&negative, 0, PythPriceMode::Spot, true)), OtcError::BadPrice.into());
+        // A conservative lower edge at or below zero is not a price
+        let wide = PythQuote { conf: 100_0000_0000, ..quote() };
+        assert_eq!(err_of(select_price_8d(&wide, 0, PythPriceMode::Conservative, false)), OtcError::BadPrice.into());
+    }
+
+    #[test]
+    fn converts_exponents_to_8_decimals() {
+        assert_eq!(convert_pyth_price(100_0000_0000, -8).unwrap(), 100_0000_0000);
+        assert_eq!(convert_pyth_price(12_345, -2).unwrap(), 123_4500_0000);
+        assert_eq!(convert_pyth_price(1_234_567_890_123, -10).unwrap(), 123_4567_8901);
+        assert_eq!(err_of(convert_pyth_price(1, 31)), OtcError::BadPrice.into());
+        assert_eq!(err_of(convert_pyth_price(i64::MAX, 0)), OtcError::Overflow.into());
+    }
+}
@@ -0,0 +1,268 @@
+//! Settlement engine shared by every payment path (fulfill_offer_usdc, fulfill_offer_sol,
+//! buy_from_consignment) and the quote views, so pricing and rounding live in one place.
+//!
+//! Rounding: the payer debit is rounded up, fees are rounded down, and the seller keeps the rest.
+
+use anchor_lang::prelude::*;
+
+use crate::{Desk, Offer, OtcError};
+
+pub(crate) fn pow10(exp: u32) -> u128 { 10u128.pow(exp) }
+pub(crate) fn mul_div_u128(a: u128, b: u128, d: u128) -> Result<u128> { a.checked_mul(b).and_then(|x| x.checked_div(d)).ok_or(OtcError::Overflow.into()) }
+pub(crate) fn mul_div_ceil_u128(a: u128, b: u128, d: u128) -> Result<u128> {
+    let prod = a.checked_mul(b).ok_or(OtcError::Overflow)?;
+    let q = prod.checked_div(d).ok_or(OtcError::Overflow)?;
+    Ok(if prod % d == 0 { q } else { q + 1 })
+}
+pub(crate) fn safe_u128_to_u64(value: u128) -> Result<u64> { u64::try_from(value).map_err(|_| OtcError::Overflow.into()) }
+
+pub(crate) fn calc_discounted_usd(token_amount: u64, price_8d: u64, decimals: u8, discount_bps: u16) -> Result<u64> {
+    require!(discount_bps <= 10_000, OtcError::Discount);
+    let token_dec = decimals as u32;
+    let usd_8d = safe_u128_to_u64(mul_div_u128(token_amount as u128, price_8d as u128, pow10(token_dec))?)?;
+    usd_8d.checked_mul(10_000 - discount_bps as u64)
+        .ok_or(OtcError::Overflow)?
+        .checked_div(10_000)
+        .ok_or(OtcError::Overflow.into())
+}
+
+/// Per-token USD price after discount
+pub(crate) fn net_price_usd_8d(price_8d: u64, discount_bps: u16) -> Result<u64> {
+    require!(discount_bps <= 10_000, OtcError::Discount);
+    bps_of(price_8d, 10_000 - discount_bps)
+}
+
+/// The consigner's reserve: the discounted per-token price may not fall below min_net_price_usd_8d (0 = none)
+pub(crate) fn check_reserve_price(min_net_price_usd_8d: u64, price_8d: u64, discount_bps: u16) -> Result<()> {
+    require!(net_price_usd_8d(price_8d, discount_bps)? >= min_net_price_usd_8d, OtcError::BelowReservePrice);
+    Ok(())
+}
+
+fn bps_of(usd_8d: u64, bps: u16) -> Result<u64> {
+    usd_8d.checked_mul(bps as u64).ok_or(OtcError::Overflow)?.checked_div(10_000).ok_or(OtcError::Overflow.into())
+}
+
+/// Currency a settlement is paid in, with the price needed to convert USD into its base units
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaymentCurrency {
+    /// Lamports at the given SOL/USD price (8 decimals)
+    Sol { sol_usd_8d: u64 },
+    /// USDC base units (6 decimals)
+    Usdc,
+}
+
+impl PaymentCurrency {
+    /// Resolve an offer's currency code; SOL offers without a price snapshot use the desk SOL price
+    pub fn for_offer(offer: &Offer, desk: &Desk) -> Result<Self> {
+        let sol_usd = if offer.sol_usd_price_8d > 0 { offer.sol_usd_price_8d } else { desk.sol_usd_price_8d };
+        Self::from_code(offer.currency, sol_usd)
+    }
+
+    pub fn from_code(currency: u8, sol_usd_8d: u64) -> Result<Self> {
+        match currency {
+            0 => {
+                require!(sol_usd_8d > 0, OtcError::NoPrice);
+                Ok(PaymentCurrency::Sol { sol_usd_8d })
+            }
+            1 => Ok(PaymentCurrency::Usdc),
+            _ => err!(OtcError::UnsupportedCurrency),
+        }
+    }
+
+    pub fn code(self) -> u8 {
+        match self {
+            PaymentCurrency::Sol { .. } => 0,
+            PaymentCurrency::Usdc => 1,
+        }
+    }
+
+    /// (numerator, denominator) converting 8-decimal USD into base units
+    fn usd_8d_ratio(self) -> (u128, u128) {
+        match self {
+            PaymentCurrency::Sol { sol_usd_8d } => (1_000_000_000, sol_usd_8d as u128),
+            PaymentCurrency::Usdc => (1_000_000, 100_000_000),
+        }
+    }
+
+    fn to_units_floor(self, usd_8d: u64) -> Result<u64> {
+        let (num, den) = self.usd_8d_ratio();
+        safe_u128_to_u64(mul_div_u128(usd_8d as u128, num, den)?)
+    }
+
+    fn to_units_ceil(self, usd_8d: u64) -> Result<u64> {
+        let (num, den) = self.usd_8d_ratio();
+        safe_u128_to_u64(mul_div_ceil_u128(usd_8d as u128, num, den)?)
+    }
+}
+
+/// Deal terms to settle
+#[derive(Clone, Copy, Debug)]
+pub struct SettlementTerms {
+    pub token_amount: u64,
+    pub price_usd_per_token_8d: u64,
+    pub token_decimals: u8,
+    pub discount_bps: u16,
+    pub commission_bps: u16,
+    pub protocol_fee_bps: u16,
+    pub referrer_bps: u16,
+}
+
+impl SettlementTerms {
+    pub fn for_offer(offer: &Offer) -> Self {
+        SettlementTerms {
+            token_amount: offer.token_amount,
+            price_usd_per_token_8d: offer.price_usd_per_token_8d,
+            token_decimals: offer.token_decimals,
+            discount_bps: offer.discount_bps,
+            commission_bps: offer.agent_commission_bps,
+            protocol_fee_bps: offer.protocol_fee_bps,
+            referrer_bps: offer.referrer_bps,
+        }
+    }
+}
+
+/// Who gets what out of one payment, in the payment currency's base units
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Settlement {
+    pub currency: u8,
+    pub gross_usd_8d: u64, // before discount
+    pub discounted_usd_8d: u64,
+    pub payer_debit: u64,
+    pub commission: u64,
+    pub protocol_fee: u64,
+    pub referrer_fee: u64,
+    pub seller_net: u64, // payer_debit - commission - protocol_fee - referrer_fee
+    pub rounding_dust: u64, // what rounding the debit up added over the exact amount
+}
+
+pub fn settle(terms: &SettlementTerms, currency: PaymentCurrency) -> Result<Settlement> {
+    require!(terms.price_usd_per_token_8d > 0, OtcError::NoPrice);
+    let gross_usd_8d = calc_discounted_usd(terms.token_amount, terms.price_usd_per_token_8d, terms.token_decimals, 0)?;
+    let discounted_usd_8d = calc_discounted_usd(terms.token_amount, terms.price_usd_per_token_8d, terms.token_decimals, terms.discount_bps)?;
+
+    let payer_debit = currency.to_units_ceil(discounted_usd_8d)?;
+    let rounding_dust = payer_debit.checked_sub(currency.to_units_floor(discounted_usd_8d)?).ok_or(OtcError::Overflow)?;
+    let commission = currency.to_units_floor(bps_of(discounted_usd_8d, terms.commission_bps)?)?;
+    let protocol_fee = currency.to_units_floor(bps_of(discounted_usd_8d, terms.protocol_fee_bps)?)?;
+    let referrer_fee = currency.to_units_floor(bps_of(discounted_usd_8d, terms.referrer_bps)?)?;
+    let seller_net = payer_debit
+        .checked_sub(commission)
+        .and_then(|x| x.checked_sub(protocol_fee))
+        .and_then(|x| x.checked_sub(referrer_fee))
+        .ok_or(OtcError::Overflow)?;
+
+    Ok(Settlement {
+        currency: currency.code(),
+        gross_usd_8d,
+        discounted_usd_8d,
+        payer_debit,
+        commission,
+        protocol_fee,
+        referrer_fee,
+        seller_net,
+        rounding_dust,
+    })
+}
+
+/// Checks shared by every fulfill path before any funds move
+/// current_price_8d is the consignment price at fulfillment, which the offer's reserve is held to
+pub fn check_fulfillable(desk: &Desk, offer: &Offer, payer: &Pubkey, treasury_balance: u64, current_price_8d: u64, now: i64) -> Result<()> {
+    require!(!desk.paused, OtcError::Paused);
+    require!(offer.approved, OtcError::NotApproved);
+    require!(!offer.cancelled && !offer.paid && !offer.fulfilled, OtcError::BadState);
+    let expiry = crate::offer_expiry(desk, offer)?;
+    require!(now <= expiry, OtcError::Expired);
+    require!(treasury_balance >= offer.token_amount, OtcError::InsuffInv);
+    check_reserve_price(offer.min_net_price_usd_8d, current_price_8d, offer.discount_bps)?;
+    if desk.restrict_fulfill {
+        require!(
+            *payer == offer.beneficiary || *payer == desk.owner || *payer == desk.agent || desk.approvers.contains(payer),
+            OtcError::FulfillRestricted
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(token_amount: u64, price_8d: u64, discount_bps: u16, commission_bps: u16) -> SettlementTerms {
+        SettlementTerms {
+            token_amount,
+            price_usd_per_token_8d: price_8d,
+            token_decimals: 9,
+            discount_bps,
+            commission_bps,
+            protocol_fee_bps: 0,
+            referrer_bps: 0,
+        }
+    }
+
+    #[test]
+    fn usdc_exact_amount_has_no_dust() {
+        // 1,000 tokens at $1.00, 10% discount => $900.00
+        let s = settle(&terms(1_000 * 1_000_000_000, 100_000_000, 1_000, 100), PaymentCurrency::Usdc).unwrap();
+        assert_eq!(s.gross_usd_8d, 100_000_000_000);
+        assert_eq!(s.discounted_usd_8d, 90_000_000_000);
+        assert_eq!(s.payer_debit, 900_000_000);
+        assert_eq!(s.rounding_dust, 0);
+        assert_eq!(s.commission, 9_000_000);
+        assert_eq!(s.seller_net, 891_000_000);
+    }
+
+    #[test]
+    fn usdc_debit_rounds_up_and_fees_round_down() {
+        // $0.00000123 is 1.23 USDC base units
+        let s = settle(&terms(123, 1_000_000_000, 0, 150), PaymentCurrency::Usdc).unwrap();
+        assert_eq!(s.discounted_usd_8d, 123);
+        assert_eq!(s.payer_debit, 2);
+        assert_eq!(s.rounding_dust, 1);
+        assert_eq!(s.commission, 0);
+        assert_eq!(s.seller_net, 2);
+    }
+
+    #[test]
+    fn sol_debit_rounds_up() {
+        // $100.00 at $150/SOL = 0.666666666.. SOL
+        let s = settle(&terms(100 * 1_000_000_000, 100_000_000, 0, 0), PaymentCurrency::Sol { sol_usd_8d: 15_000_000_000 }).unwrap();
+        assert_eq!(s.payer_debit, 666_666_667);
+        assert_eq!(s.rounding_dust, 1);
+        assert_eq!(s.seller_net, s.payer_debit);
+    }
+
+    #[test]
+    fn shares_add_up_to_debit() {
+        let mut t = terms(7_777_777_777, 333_333_333, 777, 125);
+        t.protocol_fee_bps = 40;
+        t.referrer_bps = 15;
+        let s = settle(&t, PaymentCurrency::Sol { sol_usd_8d: 17_345_678_901 }).unwrap();
+        assert!(s.protocol_fee > 0 && s.referrer_fee > 0);
+        assert_eq!(s.commission + s.protocol_fee + s.referrer_fee + s.seller_net, s.payer_debit);
+    }
+
+    #[test]
+    fn full_discount_settles_to_zero() {
+        let s = settle(&terms(1_000_000_000, 100_000_000, 10_000, 100), PaymentCurrency::Usdc).unwrap();
+        assert_eq!(s.payer_debit, 0);
+        assert_eq!(s.commission, 0);
+        assert_eq!(s.seller_net, 0);
+    }
+
+    #[test]
+    fn reserve_price_is_checked_after_discount() {
+        // $1.00 at 20% off nets $0.80
+        assert_eq!(net_price_usd_8d(100_000_000, 2_000).unwrap(), 80_000_000);
+        assert!(check_reserve_price(80_000_000, 100_000_000, 2_000).is_ok());
+        assert!(check_reserve_price(80_000_001, 100_000_000, 2_000).is_err());
+        assert!(check_reserve_price(0, 100_000_000, 10_000).is_ok());
+    }
+
+    #[test]
+    fn rejects_missing_prices_and_overflow() {
+        assert!(settle(&terms(1, 0, 0, 0), PaymentCurrency::Usdc).is_err());
+        assert!(PaymentCurrency::from_code(0, 0).is_err());
+        assert!(PaymentCurrency::from_code(2, 1).is_err());
+        assert!(settle(&terms(u64::MAX, u64::MAX, 0, 0), PaymentCurrency::Usdc).is_err());
+    }
+}
@@ -222,4 +222,33 @@ describe("otc adversarial", () => {
       "TooManyApprovers"
     );
   });
+
+  it("rejects Pyth updates below the desk's verification level", async () => {
+    // tests/fixtures/pyth-price-update-partial.json, loaded by Anchor.toml: Partial { numSignatures: 5 },
+    // feed 0x11..11, published in 2023
+    const partialPriceUpdate = new PublicKey("4PhLDG4N1uoo4M2824tE6yEAaF3QoMQaF5Tt1Jnvy464");
+    await program.methods
+      .setTokenOracleFeed(new Array(32).fill(0x11))
+      .accounts({ tokenRegistry, desk: desk.publicKey, owner: owner.publicKey })
+      .signers([owner])
+      .rpc();
+
+    const update = () =>
+      program.methods
+        .updateTokenPriceFromPyth(500)
+        .accounts({ tokenRegistry, desk: desk.publicKey, priceFeed: partialPriceUpdate, payer: owner.publicKey })
+        .signers([owner])
+        .rpc();
+
+    // Desks require Full verification by default
+    await expectRejectedWith(update(), "InsufficientVerification");
+
+    // Lowering the level lets the update through the verification gate, to the staleness check
+    await program.methods
+      .setPythVerificationLevel({ partial: { numSignatures: 5 } })
+      .accounts({ desk: desk.publicKey, owner: owner.publicKey })
+      .signers([owner])
+      .rpc();
+    await expectRejectedWith(update(), "StalePrice");
+  });
 });
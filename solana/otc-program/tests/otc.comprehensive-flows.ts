@@ -146,7 +146,8 @@ describe("OTC Comprehensive Flow Tests", () => {
           true, // is_fractionalized
           false, // is_private
           500, // max_price_volatility_bps
-          new anchor.BN(3600) // max_time_to_execute_secs
+          new anchor.BN(3600), // max_time_to_execute_secs
+          3 // accepted_currencies: SOL | USDC
         )
         .accounts({
           desk: desk.publicKey,
@@ -193,7 +194,8 @@ describe("OTC Comprehensive Flow Tests", () => {
           false, // NOT fractionalized
           false,
           0,
-          new anchor.BN(3600)
+          new anchor.BN(3600),
+          3 // accepted_currencies: SOL | USDC
         )
         .accounts({
           desk: desk.publicKey,
@@ -222,7 +224,8 @@ describe("OTC Comprehensive Flow Tests", () => {
             new anchor.BN(0), // ZERO amount
             true, 500, 30, 100, 1000, 7, 365,
             new anchor.BN("1000000000"), new anchor.BN("1000000000000000000"),
-            true, false, 500, new anchor.BN(3600)
+            true, false, 500, new anchor.BN(3600),
+            3 // accepted_currencies: SOL | USDC
           )
           .accounts({
             desk: desk.publicKey,
@@ -250,7 +253,8 @@ describe("OTC Comprehensive Flow Tests", () => {
             true, 500, 30, 100, 1000, 7, 365,
             new anchor.BN(50000 * 1e9), // min > max
             new anchor.BN(10000 * 1e9),
-            true, false, 500, new anchor.BN(3600)
+            true, false, 500, new anchor.BN(3600),
+            3 // accepted_currencies: SOL | USDC
           )
           .accounts({
             desk: desk.publicKey,
@@ -268,6 +272,64 @@ describe("OTC Comprehensive Flow Tests", () => {
       );
     });
 
+    it("should REJECT non-fractionalized consignment whose amount exceeds max deal", async () => {
+      const consignment = Keypair.generate();
+
+      await expectRejectedWith(
+        program.methods
+          .createConsignment(
+            new anchor.BN(100_000n * 10n ** 9n),
+            false, 500, 30, 0, 0, 0, 0,
+            new anchor.BN(1000 * 1e9),
+            new anchor.BN(50000 * 1e9), // max < amount, the single block could never sell
+            false, false, 0, new anchor.BN(3600),
+            3 // accepted_currencies: SOL | USDC
+          )
+          .accounts({
+            desk: desk.publicKey,
+            consigner: owner.publicKey,
+            tokenMint,
+            consignerTokenAta: ownerTokenAta,
+            deskTokenTreasury,
+            consignment: consignment.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([owner, consignment])
+          .rpc(),
+        "AmountRange"
+      );
+    });
+
+    it("should REJECT consignment with execution window under 60s", async () => {
+      const consignment = Keypair.generate();
+
+      await expectRejectedWith(
+        program.methods
+          .createConsignment(
+            new anchor.BN(100_000n * 10n ** 9n),
+            true, 500, 30, 100, 1000, 7, 365,
+            new anchor.BN(1000 * 1e9), new anchor.BN(50000 * 1e9),
+            true, false, 500,
+            new anchor.BN(30), // below the 60s floor
+            3 // accepted_currencies: SOL | USDC
+          )
+          .accounts({
+            desk: desk.publicKey,
+            consigner: owner.publicKey,
+            tokenMint,
+            consignerTokenAta: ownerTokenAta,
+            deskTokenTreasury,
+            consignment: consignment.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([owner, consignment])
+          .rpc(),
+        "ExecuteWindow"
+      );
+    });
+
     it("should REJECT consignment with discount > 100%", async () => {
       const consignment = Keypair.generate();
 
@@ -279,7 +341,8 @@ describe("OTC Comprehensive Flow Tests", () => {
             10001, // > 100%
             7, 365,
             new anchor.BN("1000000000"), new anchor.BN("1000000000000000000"),
-            true, false, 500, new anchor.BN(3600)
+            true, false, 500, new anchor.BN(3600),
+            3 // accepted_currencies: SOL | USDC
           )
           .accounts({
             desk: desk.publicKey,
@@ -312,7 +375,8 @@ describe("OTC Comprehensive Flow Tests", () => {
             new anchor.BN("100000000000000"), // 100k tokens
             true, 500, 30, 100, 1000, 7, 365,
             new anchor.BN("1000000000"), new anchor.BN("1000000000000000000"),
-            true, false, 500, new anchor.BN(3600)
+            true, false, 500, new anchor.BN(3600),
+            3 // accepted_currencies: SOL | USDC
           )
           .accounts({
             desk: desk.publicKey,
@@ -355,7 +419,8 @@ describe("OTC Comprehensive Flow Tests", () => {
           true, 500, 0, 100, 1000, 0, 365,
           new anchor.BN(1000 * 1e9),
           new anchor.BN(50000 * 1e9),
-          true, false, 500, new anchor.BN(3600)
+          true, false, 500, new anchor.BN(3600),
+          3 // accepted_currencies: SOL | USDC
         )
         .accounts({
           desk: desk.publicKey,
@@ -404,7 +469,8 @@ describe("OTC Comprehensive Flow Tests", () => {
           discountBps,
           1, // USDC
           new anchor.BN(0), // no lockup
-          agentCommissionBps
+          agentCommissionBps,
+          [] // allowlist_proof
         )
         .accounts({
           desk: desk.publicKey,
@@ -457,7 +523,6 @@ describe("OTC Comprehensive Flow Tests", () => {
           deskTokenTreasury,
           deskUsdcTreasury,
           payerUsdcAta: buyerUsdcAta,
-          agentUsdcAta,
           deskSigner: desk.publicKey,
           payer: buyer.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
@@ -474,9 +539,30 @@ describe("OTC Comprehensive Flow Tests", () => {
       const buyerUsdcAfter = (await getAccount(provider.connection, buyerUsdcAta)).amount;
       assert.isTrue(buyerUsdcAfter < buyerUsdcBefore);
 
-      // Verify commission paid to agent
+      // Verify commission accrued to agent, then claim it
+      const [agentCommission] = PublicKey.findProgramAddressSync(
+        [Buffer.from("commission"), desk.publicKey.toBuffer(), agent.publicKey.toBuffer(), Buffer.from([1])],
+        program.programId
+      );
+      const accrual = await program.account.commissionAccrual.fetch(agentCommission);
+      assert.isTrue(accrual.accruedAmount.toNumber() > 0);
+
+      await program.methods
+        .claimCommission(1)
+        .accounts({
+          desk: desk.publicKey,
+          deskSigner: desk.publicKey,
+          recipient: agent.publicKey,
+          usdcMint,
+          deskUsdcTreasury,
+          recipientUsdcAta: agentUsdcAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([agent, desk])
+        .rpc();
+
       const agentUsdcBalance = (await getAccount(provider.connection, agentUsdcAta)).amount;
-      assert.isTrue(Number(agentUsdcBalance) > 0);
+      assert.equal(agentUsdcBalance.toString(), accrual.accruedAmount.toString());
 
       // 4. Claim tokens
       await program.methods
@@ -513,7 +599,8 @@ describe("OTC Comprehensive Flow Tests", () => {
           0, 0, 0, 0, 0,
           new anchor.BN(1000 * 1e9),
           new anchor.BN(50000 * 1e9),
-          true, false, 0, new anchor.BN(3600)
+          true, false, 0, new anchor.BN(3600),
+          3 // accepted_currencies: SOL | USDC
         )
         .accounts({
           desk: desk.publicKey,
@@ -539,7 +626,8 @@ describe("OTC Comprehensive Flow Tests", () => {
           300, // must match fixed discount
           1, 
           new anchor.BN(0), // must match fixed lockup (0)
-          0 // ignored for P2P
+          0, // ignored for P2P
+          [] // allowlist_proof
         )
         .accounts({
           desk: desk.publicKey,
@@ -573,7 +661,8 @@ describe("OTC Comprehensive Flow Tests", () => {
           500, 0, 0, 0, 0, 0,
           new anchor.BN("10000000000"), // 10 tokens min
           new anchor.BN("50000000000000"), // 50k tokens max
-          true, false, 0, new anchor.BN(3600)
+          true, false, 0, new anchor.BN(3600),
+          3 // accepted_currencies: SOL | USDC
         )
         .accounts({
           desk: desk.publicKey,
@@ -602,7 +691,8 @@ describe("OTC Comprehensive Flow Tests", () => {
           new anchor.BN(consignmentAccount.id.toString()),
           tokenAmount,
           500, 0, // currency = SOL
-          new anchor.BN(0), 0
+          new anchor.BN(0), 0,
+          [] // allowlist_proof
         )
         .accounts({
           desk: desk.publicKey,
@@ -629,7 +719,6 @@ describe("OTC Comprehensive Flow Tests", () => {
           desk: desk.publicKey,
           offer: offer.publicKey,
           deskTokenTreasury,
-          agent: null, // no commission for P2P in this test
           deskSigner: desk.publicKey,
           payer: buyer.publicKey,
           systemProgram: SystemProgram.programId,
@@ -686,7 +775,8 @@ describe("OTC Comprehensive Flow Tests", () => {
           true, 500, 0, 100, 1000, 0, 365,
           new anchor.BN(1000 * 1e9),
           new anchor.BN(50000 * 1e9),
-          true, false, 500, new anchor.BN(3600)
+          true, false, 500, new anchor.BN(3600),
+          3 // accepted_currencies: SOL | USDC
         )
         .accounts({
           desk: desk.publicKey,
@@ -716,7 +806,8 @@ describe("OTC Comprehensive Flow Tests", () => {
         .createOfferFromConsignment(
           new anchor.BN(consignmentAccount.id.toString()),
           new anchor.BN(10_000n * 10n ** 9n),
-          500, 1, new anchor.BN(0), 50
+          500, 1, new anchor.BN(0), 50,
+          [] // allowlist_proof
         )
         .accounts({
           desk: desk.publicKey,
@@ -760,7 +851,8 @@ describe("OTC Comprehensive Flow Tests", () => {
         .createOfferFromConsignment(
           new anchor.BN(consignmentAccount.id.toString()),
           new anchor.BN(10_000n * 10n ** 9n),
-          500, 1, new anchor.BN(0), 50
+          500, 1, new anchor.BN(0), 50,
+          [] // allowlist_proof
         )
         .accounts({
           desk: desk.publicKey,
@@ -797,7 +889,8 @@ describe("OTC Comprehensive Flow Tests", () => {
         .createOfferFromConsignment(
           new anchor.BN(consignmentAccount.id.toString()),
           new anchor.BN(10_000n * 10n ** 9n),
-          500, 1, new anchor.BN(0), 50
+          500, 1, new anchor.BN(0), 50,
+          [] // allowlist_proof
         )
         .accounts({
           desk: desk.publicKey,
@@ -936,7 +1029,8 @@ describe("OTC Comprehensive Flow Tests", () => {
           true, 500, 0, 100, 1000, 0, 365,
           new anchor.BN(1000 * 1e9),
           new anchor.BN(50000 * 1e9),
-          true, false, 500, new anchor.BN(3600)
+          true, false, 500, new anchor.BN(3600),
+          3 // accepted_currencies: SOL | USDC
         )
         .accounts({
           desk: desk.publicKey,
@@ -991,7 +1085,8 @@ describe("OTC Comprehensive Flow Tests", () => {
           true, 500, 0, 100, 1000, 0, 365,
           new anchor.BN(1000 * 1e9),
           new anchor.BN(50000 * 1e9),
-          true, false, 500, new anchor.BN(3600)
+          true, false, 500, new anchor.BN(3600),
+          3 // accepted_currencies: SOL | USDC
         )
         .accounts({
           desk: desk.publicKey,
@@ -1044,7 +1139,8 @@ describe("OTC Comprehensive Flow Tests", () => {
           true, 500, 0, 100, 1000, 0, 365,
           new anchor.BN("1000000000000"),
           new anchor.BN("50000000000000"),
-          true, false, 500, new anchor.BN(3600)
+          true, false, 500, new anchor.BN(3600),
+          3 // accepted_currencies: SOL | USDC
         )
         .accounts({
           desk: desk.publicKey,
@@ -1069,7 +1165,8 @@ describe("OTC Comprehensive Flow Tests", () => {
             new anchor.BN(consignmentAccount.id.toString()),
             new anchor.BN("10000000000000"),
             500, 1, new anchor.BN(0),
-            10 // Too low - min is 25 bps
+            10, // Too low - min is 25 bps
+            [] // allowlist_proof
           )
           .accounts({
             desk: desk.publicKey,
@@ -1093,7 +1190,8 @@ describe("OTC Comprehensive Flow Tests", () => {
           true, 500, 0, 100, 1000, 0, 365,
           new anchor.BN("1000000000000"),
           new anchor.BN("50000000000000"),
-          true, false, 500, new anchor.BN(3600)
+          true, false, 500, new anchor.BN(3600),
+          3 // accepted_currencies: SOL | USDC
         )
         .accounts({
           desk: desk.publicKey,
@@ -1118,7 +1216,8 @@ describe("OTC Comprehensive Flow Tests", () => {
             new anchor.BN(consignmentAccount.id.toString()),
             new anchor.BN("10000000000000"),
             500, 1, new anchor.BN(0),
-            200 // Too high - max is 150 bps
+            200, // Too high - max is 150 bps
+            [] // allowlist_proof
           )
           .accounts({
             desk: desk.publicKey,
@@ -1149,7 +1248,8 @@ describe("OTC Comprehensive Flow Tests", () => {
           0, 0, 0, 0,
           new anchor.BN(1000 * 1e9),
           new anchor.BN(50000 * 1e9),
-          true, false, 0, new anchor.BN(3600)
+          true, false, 0, new anchor.BN(3600),
+          3 // accepted_currencies: SOL | USDC
         )
         .accounts({
           desk: desk.publicKey,
@@ -1174,7 +1274,8 @@ describe("OTC Comprehensive Flow Tests", () => {
           new anchor.BN(10_000n * 10n ** 9n),
           500, 1, 
           new anchor.BN(86400), // 1 day lockup
-          0
+          0,
+          [] // allowlist_proof
         )
         .accounts({
           desk: desk.publicKey,
@@ -1204,7 +1305,6 @@ describe("OTC Comprehensive Flow Tests", () => {
           deskTokenTreasury,
           deskUsdcTreasury,
           payerUsdcAta: buyerUsdcAta,
-          agentUsdcAta: null,
           deskSigner: desk.publicKey,
           payer: buyer.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
@@ -1263,7 +1363,8 @@ describe("OTC Comprehensive Flow Tests", () => {
           false, 0, 0, 0, 0, 0, 0,
           new anchor.BN(1), // min 1 unit
           new anchor.BN("1000000000"),
-          true, false, 0, new anchor.BN(3600)
+          true, false, 0, new anchor.BN(3600),
+          3 // accepted_currencies: SOL | USDC
         )
         .accounts({
           desk: desk.publicKey,
@@ -1286,7 +1387,8 @@ describe("OTC Comprehensive Flow Tests", () => {
         .createOfferFromConsignment(
           new anchor.BN(consignmentAccount.id.toString()),
           new anchor.BN("1000000000"),
-          0, 1, new anchor.BN(0), 0
+          0, 1, new anchor.BN(0), 0,
+          [] // allowlist_proof
         )
         .accounts({
           desk: desk.publicKey,
@@ -1324,7 +1426,8 @@ describe("OTC Comprehensive Flow Tests", () => {
           false, 0, 0, 0, 0, 0, 0,
           new anchor.BN("1000000000"),
           new anchor.BN("100000000000000"),
-          true, false, 0, new anchor.BN(3600)
+          true, false, 0, new anchor.BN(3600),
+          3 // accepted_currencies: SOL | USDC
         )
         .accounts({
           desk: desk.publicKey,
@@ -1348,7 +1451,8 @@ describe("OTC Comprehensive Flow Tests", () => {
           .createOfferFromConsignment(
             new anchor.BN(consignmentAccount.id.toString()),
             new anchor.BN("1000000000"), // 1 token = $10 < $100 min
-            0, 1, new anchor.BN(0), 0
+            0, 1, new anchor.BN(0), 0,
+            [] // allowlist_proof
           )
           .accounts({
             desk: desk.publicKey,
@@ -1383,7 +1487,8 @@ describe("OTC Comprehensive Flow Tests", () => {
           false, 500, 0, 0, 0, 0, 0,
           new anchor.BN(1000 * 1e9),
           new anchor.BN(50000 * 1e9),
-          true, false, 0, new anchor.BN(3600)
+          true, false, 0, new anchor.BN(3600),
+          3 // accepted_currencies: SOL | USDC
         )
         .accounts({
           desk: desk.publicKey,
@@ -1405,7 +1510,8 @@ describe("OTC Comprehensive Flow Tests", () => {
         .createOfferFromConsignment(
           new anchor.BN(consignmentAccount.id.toString()),
           new anchor.BN(10_000n * 10n ** 9n),
-          500, 1, new anchor.BN(0), 0
+          500, 1, new anchor.BN(0), 0,
+          [] // allowlist_proof
         )
         .accounts({
           desk: desk.publicKey,
@@ -1442,7 +1548,6 @@ describe("OTC Comprehensive Flow Tests", () => {
             deskTokenTreasury,
             deskUsdcTreasury,
             payerUsdcAta: thirdPartyUsdc,
-            agentUsdcAta: null,
             deskSigner: desk.publicKey,
             payer: thirdParty.publicKey,
             tokenProgram: TOKEN_PROGRAM_ID,
@@ -1463,7 +1568,6 @@ describe("OTC Comprehensive Flow Tests", () => {
           deskTokenTreasury,
           deskUsdcTreasury,
           payerUsdcAta: buyerUsdcAta,
-          agentUsdcAta: null,
           deskSigner: desk.publicKey,
           payer: buyer.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
@@ -1524,7 +1628,8 @@ describe("OTC Comprehensive Flow Tests", () => {
           true, 500, 0, 100, 1000, 0, 365,
           new anchor.BN(1000 * 1e9),
           new anchor.BN(50000 * 1e9),
-          true, false, 500, new anchor.BN(3600)
+          true, false, 500, new anchor.BN(3600),
+          3 // accepted_currencies: SOL | USDC
         )
         .accounts({
           desk: desk.publicKey,
@@ -1551,7 +1656,8 @@ describe("OTC Comprehensive Flow Tests", () => {
         .createOfferFromConsignment(
           new anchor.BN(consignmentAccount.id.toString()),
           new anchor.BN(10_000n * 10n ** 9n),
-          500, 1, new anchor.BN(0), 50
+          500, 1, new anchor.BN(0), 50,
+          [] // allowlist_proof
         )
         .accounts({
           desk: desk.publicKey,
@@ -1602,7 +1708,8 @@ describe("OTC Comprehensive Flow Tests", () => {
           false, 500, 0, 0, 0, 0, 0,
           new anchor.BN(1000 * 1e9),
           new anchor.BN(50000 * 1e9),
-          true, false, 0, new anchor.BN(3600)
+          true, false, 0, new anchor.BN(3600),
+          3 // accepted_currencies: SOL | USDC
         )
         .accounts({
           desk: desk.publicKey,
@@ -1624,7 +1731,8 @@ describe("OTC Comprehensive Flow Tests", () => {
         .createOfferFromConsignment(
           new anchor.BN(consignmentAccount.id.toString()),
           new anchor.BN(10_000n * 10n ** 9n),
-          500, 1, new anchor.BN(0), 0
+          500, 1, new anchor.BN(0), 0,
+          [] // allowlist_proof
         )
         .accounts({
           desk: desk.publicKey,
@@ -1654,7 +1762,6 @@ describe("OTC Comprehensive Flow Tests", () => {
           deskTokenTreasury,
           deskUsdcTreasury,
           payerUsdcAta: buyerUsdcAta,
-          agentUsdcAta: null,
           deskSigner: desk.publicKey,
           payer: buyer.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
@@ -1674,7 +1781,6 @@ describe("OTC Comprehensive Flow Tests", () => {
             deskTokenTreasury,
             deskUsdcTreasury,
             payerUsdcAta: buyerUsdcAta,
-            agentUsdcAta: null,
             deskSigner: desk.publicKey,
             payer: buyer.publicKey,
             tokenProgram: TOKEN_PROGRAM_ID,
@@ -0,0 +1,1463 @@
+// Consignment Feature Tests - term updates, top-ups, expiry, auctions, bids, pricing modes
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Otc } from "../target/types/otc";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+  LAMPORTS_PER_SOL,
+  Ed25519Program,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+} from "@solana/web3.js";
+import {
+  createMint,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+  TOKEN_PROGRAM_ID,
+  getAssociatedTokenAddressSync,
+  getAccount,
+} from "@solana/spl-token";
+import { assert } from "chai";
+import { keccak_256 } from "@noble/hashes/sha3";
+
+async function expectRejectedWith(promise: Promise<unknown>, expectedError: string): Promise<void> {
+  try {
+    await promise;
+    assert.fail("Expected promise to reject but it resolved");
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    assert.include(errorMessage, expectedError, `Expected error containing "${expectedError}" but got: ${errorMessage}`);
+  }
+}
+
+describe("OTC Consignment Feature Tests", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.Otc as Program<Otc>;
+
+  const airdrop = async (pk: PublicKey, lamports: number) => {
+    const sig = await provider.connection.requestAirdrop(pk, lamports);
+    await provider.connection.confirmTransaction(sig, "confirmed");
+  };
+
+  const getTokenRegistryPda = (desk: PublicKey, tokenMint: PublicKey) => {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("registry"), desk.toBuffer(), tokenMint.toBuffer()],
+      program.programId
+    )[0];
+  };
+
+  const TOKENS = (n: number) => new anchor.BN(n).mul(new anchor.BN(10).pow(new anchor.BN(9)));
+
+  let owner: Keypair;
+  let agent: Keypair;
+  let buyer: Keypair;
+  let desk: Keypair;
+  let tokenMint: PublicKey;
+  let usdcMint: PublicKey;
+  let tokenRegistry: PublicKey;
+  let deskTokenTreasury: PublicKey;
+  let deskUsdcTreasury: PublicKey;
+  let ownerTokenAta: PublicKey;
+  let buyerUsdcAta: PublicKey;
+
+  beforeEach(async () => {
+    owner = Keypair.generate();
+    agent = Keypair.generate();
+    buyer = Keypair.generate();
+    desk = Keypair.generate();
+
+    await Promise.all([
+      airdrop(owner.publicKey, 10 * LAMPORTS_PER_SOL),
+      airdrop(agent.publicKey, 2 * LAMPORTS_PER_SOL),
+      airdrop(buyer.publicKey, 10 * LAMPORTS_PER_SOL),
+    ]);
+    await new Promise(r => setTimeout(r, 500));
+
+    tokenMint = await createMint(provider.connection, owner, owner.publicKey, null, 9);
+    usdcMint = await createMint(provider.connection, owner, owner.publicKey, null, 6);
+
+    deskTokenTreasury = getAssociatedTokenAddressSync(tokenMint, desk.publicKey, true);
+    deskUsdcTreasury = getAssociatedTokenAddressSync(usdcMint, desk.publicKey, true);
+    tokenRegistry = getTokenRegistryPda(desk.publicKey, tokenMint);
+
+    await getOrCreateAssociatedTokenAccount(provider.connection, owner, tokenMint, desk.publicKey, true);
+    await getOrCreateAssociatedTokenAccount(provider.connection, owner, usdcMint, desk.publicKey, true);
+
+    await program.methods
+      .initDesk(new anchor.BN(1 * 1e8), new anchor.BN(1800)) // $1 min, 30 min expiry
+      .accounts({
+        payer: owner.publicKey,
+        owner: owner.publicKey,
+        agent: agent.publicKey,
+        usdcMint,
+        desk: desk.publicKey,
+      })
+      .signers([owner, desk])
+      .rpc();
+
+    await program.methods
+      .registerToken(Array(32).fill(0), PublicKey.default, 0)
+      .accounts({ desk: desk.publicKey, payer: owner.publicKey, tokenMint })
+      .signers([owner])
+      .rpc();
+
+    // $10 per token, $100 per SOL
+    await program.methods
+      .setManualTokenPrice(new anchor.BN(10 * 1e8))
+      .accounts({ tokenRegistry, desk: desk.publicKey, owner: owner.publicKey })
+      .signers([owner])
+      .rpc();
+    await program.methods
+      .setPrices(new anchor.BN(10 * 1e8), new anchor.BN(100 * 1e8), new anchor.BN(0), new anchor.BN(3600))
+      .accounts({ desk: desk.publicKey, owner: owner.publicKey })
+      .signers([owner])
+      .rpc();
+
+    ownerTokenAta = getAssociatedTokenAddressSync(tokenMint, owner.publicKey);
+    await getOrCreateAssociatedTokenAccount(provider.connection, owner, tokenMint, owner.publicKey);
+    await mintTo(provider.connection, owner, tokenMint, ownerTokenAta, owner, 1_000_000n * 10n ** 9n);
+
+    buyerUsdcAta = (await getOrCreateAssociatedTokenAccount(
+      provider.connection, buyer, usdcMint, buyer.publicKey
+    )).address;
+    await mintTo(provider.connection, owner, usdcMint, buyerUsdcAta, owner, 1_000_000n * 10n ** 6n);
+  });
+
+  // Negotiable, fractionalized 100k token consignment unless overridden
+  const createConsignment = async (opts: {
+    amount?: anchor.BN;
+    negotiable?: boolean;
+    fixedDiscountBps?: number;
+    fixedLockupDays?: number;
+    fractionalized?: boolean;
+    minDeal?: anchor.BN;
+    maxDeal?: anchor.BN;
+    acceptedCurrencies?: number;
+  } = {}): Promise<Keypair> => {
+    const consignment = Keypair.generate();
+    const amount = opts.amount ?? TOKENS(100_000);
+    await program.methods
+      .createConsignment(
+        amount,
+        opts.negotiable ?? true,
+        opts.fixedDiscountBps ?? 500,
+        opts.fixedLockupDays ?? 0,
+        100, 1000, 0, 365,
+        opts.minDeal ?? TOKENS(1000),
+        opts.maxDeal ?? TOKENS(50_000),
+        opts.fractionalized ?? true,
+        false, 500, new anchor.BN(3600),
+        opts.acceptedCurrencies ?? 3 // SOL | USDC
+      )
+      .accounts({
+        desk: desk.publicKey,
+        consigner: owner.publicKey,
+        tokenMint,
+        consignerTokenAta: ownerTokenAta,
+        deskTokenTreasury,
+        consignment: consignment.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owner, consignment])
+      .rpc();
+    return consignment;
+  };
+
+  const consignmentId = async (consignment: Keypair) =>
+    new anchor.BN((await program.account.consignment.fetch(consignment.publicKey)).id.toString());
+
+  const withdrawAccounts = (consignment: Keypair) => ({
+    consignment: consignment.publicKey,
+    desk: desk.publicKey,
+    tokenMint,
+    deskSigner: desk.publicKey,
+    consigner: owner.publicKey,
+    deskTokenTreasury,
+    consignerTokenAta: ownerTokenAta,
+    tokenProgram: TOKEN_PROGRAM_ID,
+  });
+
+  const topUpAccounts = (consignment: Keypair) => ({
+    desk: desk.publicKey,
+    consignment: consignment.publicKey,
+    consigner: owner.publicKey,
+    tokenMint,
+    consignerTokenAta: ownerTokenAta,
+    deskTokenTreasury,
+    tokenProgram: TOKEN_PROGRAM_ID,
+  });
+
+  const updateAccounts = (consignment: Keypair) => ({
+    desk: desk.publicKey,
+    consignment: consignment.publicKey,
+    consigner: owner.publicKey,
+  });
+
+  // P2P purchase paid in USDC; returns the paid offer
+  const buy = async (consignment: Keypair, tokenAmount: anchor.BN, maxPayment = new anchor.BN(1_000_000 * 1e6)) => {
+    const offer = Keypair.generate();
+    await program.methods
+      .buyFromConsignment(await consignmentId(consignment), tokenAmount, 1, maxPayment, [])
+      .accounts({
+        desk: desk.publicKey,
+        consignment: consignment.publicKey,
+        tokenRegistry,
+        deskTokenTreasury,
+        beneficiary: buyer.publicKey,
+        offer: offer.publicKey,
+        usdcMint,
+        deskUsdcTreasury,
+        beneficiaryUsdcAta: buyerUsdcAta,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([buyer, offer])
+      .rpc();
+    return offer;
+  };
+
+  // Negotiable offer awaiting approval
+  const requestOffer = async (consignment: Keypair, tokenAmount = TOKENS(1000), discountBps = 500) => {
+    const offer = Keypair.generate();
+    await program.methods
+      .createOfferFromConsignment(await consignmentId(consignment), tokenAmount, discountBps, 1, new anchor.BN(0), 50, [])
+      .accounts({
+        desk: desk.publicKey,
+        consignment: consignment.publicKey,
+        tokenRegistry,
+        beneficiary: buyer.publicKey,
+        offer: offer.publicKey,
+      })
+      .signers([buyer, offer])
+      .rpc();
+    return offer;
+  };
+
+  // =====================================================
+  // CONSIGNMENT STATE (TOP-UP / WITHDRAW / ALLOWLIST)
+  // =====================================================
+  describe("Consignment State", () => {
+    it("should top up an active consignment", async () => {
+      const consignment = await createConsignment();
+      const id = await consignmentId(consignment);
+
+      await program.methods
+        .topUpConsignment(id, TOKENS(5000))
+        .accounts(topUpAccounts(consignment))
+        .signers([owner])
+        .rpc();
+
+      const after = await program.account.consignment.fetch(consignment.publicKey);
+      assert.equal(after.remainingAmount.toString(), TOKENS(105_000).toString());
+      assert.isTrue(after.isActive);
+    });
+
+    it("should REJECT top-up of a withdrawn consignment", async () => {
+      const consignment = await createConsignment();
+      const id = await consignmentId(consignment);
+
+      await program.methods
+        .withdrawConsignment(id)
+        .accounts(withdrawAccounts(consignment))
+        .signers([owner, desk])
+        .rpc();
+
+      await expectRejectedWith(
+        program.methods
+          .topUpConsignment(id, TOKENS(5000))
+          .accounts(topUpAccounts(consignment))
+          .signers([owner])
+          .rpc(),
+        "BadState"
+      );
+      const after = await program.account.consignment.fetch(consignment.publicKey);
+      assert.isFalse(after.isActive);
+      assert.isTrue(after.isWithdrawn);
+    });
+
+    it("should REJECT setting an allowlist on a withdrawn consignment", async () => {
+      const consignment = await createConsignment();
+      const id = await consignmentId(consignment);
+
+      await program.methods
+        .withdrawConsignment(id)
+        .accounts(withdrawAccounts(consignment))
+        .signers([owner, desk])
+        .rpc();
+
+      await expectRejectedWith(
+        program.methods
+          .setConsignmentAllowlist(id, Array(32).fill(1))
+          .accounts({ desk: desk.publicKey, consignment: consignment.publicKey, consigner: owner.publicKey })
+          .signers([owner])
+          .rpc(),
+        "BadState"
+      );
+    });
+
+    it("should migrate legacy-size accounts with behaviour-preserving defaults", async () => {
+      // tests/fixtures/legacy-*.json, loaded by Anchor.toml at their pre-upgrade sizes
+      const legacyDesk = new PublicKey("93pANqas4qtvnTVz7CRvgd8k85EzYicnjyEwskrMT1X2");
+      const activeConsignment = new PublicKey("2v12KBLP5FaP1xAb2FbodHAstx7vvy98q56ShHnVKU9W");
+      const withdrawnConsignment = new PublicKey("JBEdjnH6pwPBAj4LWGUAt7tvwhhd7rz7CVGP9mk4W6KQ");
+
+      for (const account of [legacyDesk, activeConsignment, withdrawnConsignment]) {
+        const before = (await provider.connection.getAccountInfo(account))!.data.length;
+        await program.methods
+          .migrateAccount()
+          .accounts({ payer: buyer.publicKey, account })
+          .signers([buyer])
+          .rpc();
+        const after = (await provider.connection.getAccountInfo(account))!.data.length;
+        assert.isAbove(after, before);
+      }
+
+      const migratedDesk = await program.account.desk.fetch(legacyDesk);
+      assert.deepEqual(migratedDesk.minPythVerification, { full: {} });
+      assert.equal(migratedDesk.p2pCommissionBps, 25);
+      assert.equal(migratedDesk.nextOfferId.toNumber(), 5);
+
+      const active = await program.account.consignment.fetch(activeConsignment);
+      assert.equal(active.acceptedCurrencies, 3);
+      assert.isFalse(active.isWithdrawn);
+      assert.equal(active.remainingAmount.toNumber(), 1000);
+
+      // Legacy withdrawals only cleared is_active
+      const withdrawn = await program.account.consignment.fetch(withdrawnConsignment);
+      assert.equal(withdrawn.acceptedCurrencies, 3);
+      assert.isTrue(withdrawn.isWithdrawn);
+
+      await expectRejectedWith(
+        program.methods
+          .migrateAccount()
+          .accounts({ payer: buyer.publicKey, account: legacyDesk })
+          .signers([buyer])
+          .rpc(),
+        "BadState"
+      );
+    });
+
+    it("should REJECT migrating accounts already at the current layout", async () => {
+      const consignment = await createConsignment();
+
+      for (const account of [desk.publicKey, consignment.publicKey, tokenRegistry]) {
+        await expectRejectedWith(
+          program.methods
+            .migrateAccount()
+            .accounts({ payer: buyer.publicKey, account })
+            .signers([buyer])
+            .rpc(),
+          "BadState"
+        );
+      }
+    });
+  });
+
+  // =====================================================
+  // CONSIGNMENT EXPIRY
+  // =====================================================
+  describe("Consignment Expiry", () => {
+    const setExpiry = async (consignment: Keypair, secsFromNow: number) => {
+      const now = Math.floor(Date.now() / 1000);
+      await program.methods
+        .setConsignmentExpiry(await consignmentId(consignment), new anchor.BN(now + secsFromNow))
+        .accounts({ desk: desk.publicKey, consignment: consignment.publicKey, consigner: owner.publicKey })
+        .signers([owner])
+        .rpc();
+    };
+
+    it("should let anyone delist an expired consignment and the desk sweep it back", async () => {
+      const consignment = await createConsignment();
+      const id = await consignmentId(consignment);
+      await setExpiry(consignment, 2);
+      await new Promise(r => setTimeout(r, 4000));
+
+      // The crank needs no desk signature
+      await program.methods
+        .expireConsignment(id)
+        .accounts({ desk: desk.publicKey, consignment: consignment.publicKey, caller: buyer.publicKey })
+        .signers([buyer])
+        .rpc();
+
+      let after = await program.account.consignment.fetch(consignment.publicKey);
+      assert.isFalse(after.isActive);
+      assert.equal(after.remainingAmount.toString(), TOKENS(100_000).toString());
+
+      const ownerBalanceBefore = (await getAccount(provider.connection, ownerTokenAta)).amount;
+      await program.methods
+        .sweepExpiredConsignment(id)
+        .accounts({
+          desk: desk.publicKey,
+          deskSigner: desk.publicKey,
+          consignment: consignment.publicKey,
+          tokenMint,
+          deskTokenTreasury,
+          consignerTokenAta: ownerTokenAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([desk])
+        .rpc();
+
+      after = await program.account.consignment.fetch(consignment.publicKey);
+      assert.equal(after.remainingAmount.toString(), "0");
+      assert.isTrue(after.isWithdrawn);
+      const ownerBalanceAfter = (await getAccount(provider.connection, ownerTokenAta)).amount;
+      assert.equal((ownerBalanceAfter - ownerBalanceBefore).toString(), TOKENS(100_000).toString());
+    });
+
+    it("should REJECT expiring a consignment before its expiry", async () => {
+      const consignment = await createConsignment();
+      await setExpiry(consignment, 3600);
+
+      await expectRejectedWith(
+        program.methods
+          .expireConsignment(await consignmentId(consignment))
+          .accounts({ desk: desk.publicKey, consignment: consignment.publicKey, caller: buyer.publicKey })
+          .signers([buyer])
+          .rpc(),
+        "NotExpired"
+      );
+    });
+  });
+
+  // =====================================================
+  // COMPLIANCE SCREENING
+  // =====================================================
+  describe("Compliance Screening", () => {
+    const setCompliance = async (requiredMask: number) => {
+      await program.methods
+        .setComplianceConfig(requiredMask, Array(8).fill(new anchor.BN(0)))
+        .accounts({ owner: owner.publicKey, desk: desk.publicKey })
+        .signers([owner])
+        .rpc();
+    };
+
+    const attest = async (wallet: PublicKey, denied: boolean) => {
+      await program.methods
+        .setAttestation(wallet, 0, new anchor.BN(0), denied)
+        .accounts({ desk: desk.publicKey, issuer: owner.publicKey })
+        .signers([owner])
+        .rpc();
+    };
+
+    const createOffer = async (consignment: Keypair) => {
+      const offer = Keypair.generate();
+      await program.methods
+        .createOfferFromConsignment(
+          await consignmentId(consignment),
+          TOKENS(1000),
+          500,
+          1, // USDC
+          new anchor.BN(0),
+          50,
+          []
+        )
+        .accounts({
+          desk: desk.publicKey,
+          consignment: consignment.publicKey,
+          tokenRegistry,
+          beneficiary: buyer.publicKey,
+          offer: offer.publicKey,
+        })
+        .signers([buyer, offer])
+        .rpc();
+      return offer;
+    };
+
+    it("should create an offer for a wallet with no attestation when compliance is not gated", async () => {
+      const consignment = await createConsignment();
+      await setCompliance(0);
+
+      const offer = await createOffer(consignment);
+      const offerAccount = await program.account.offer.fetch(offer.publicKey);
+      assert.equal(offerAccount.beneficiary.toBase58(), buyer.publicKey.toBase58());
+    });
+
+    it("should REJECT a denylisted beneficiary even when compliance is not gated", async () => {
+      const consignment = await createConsignment();
+      await setCompliance(0);
+      await attest(buyer.publicKey, true);
+
+      await expectRejectedWith(createOffer(consignment), "WalletDenied");
+    });
+
+    it("should REJECT a missing attestation when offer creation is gated", async () => {
+      const consignment = await createConsignment();
+      await setCompliance(2); // COMPLIANCE_CREATE_OFFER_FROM_CONSIGNMENT
+
+      await expectRejectedWith(createOffer(consignment), "AttestationRequired");
+    });
+
+    it("should REJECT a denylisted third party paying for someone else's offer", async () => {
+      const consignment = await createConsignment();
+      await setCompliance(0);
+      const offer = await createOffer(consignment);
+      await program.methods
+        .approveOffer((await program.account.offer.fetch(offer.publicKey)).id)
+        .accounts({ desk: desk.publicKey, offer: offer.publicKey, consignment: consignment.publicKey, approver: agent.publicKey })
+        .signers([agent])
+        .rpc();
+
+      const payer = Keypair.generate();
+      await airdrop(payer.publicKey, 2 * LAMPORTS_PER_SOL);
+      const payerUsdcAta = (await getOrCreateAssociatedTokenAccount(provider.connection, payer, usdcMint, payer.publicKey)).address;
+      await mintTo(provider.connection, owner, usdcMint, payerUsdcAta, owner, 100_000n * 10n ** 6n);
+      await attest(payer.publicKey, true);
+
+      await expectRejectedWith(
+        program.methods
+          .fulfillOfferUsdc((await program.account.offer.fetch(offer.publicKey)).id)
+          .accounts({
+            desk: desk.publicKey,
+            offer: offer.publicKey,
+            consignment: null,
+            tokenRegistry: null,
+            usdcMint,
+            deskTokenTreasury,
+            deskUsdcTreasury,
+            payerUsdcAta,
+            deskSigner: desk.publicKey,
+            payer: payer.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([payer, desk])
+          .rpc(),
+        "WalletDenied"
+      );
+    });
+  });
+
+  // =====================================================
+  // AGENT-SIGNED QUOTES
+  // =====================================================
+  describe("Signed Quotes", () => {
+    type Quote = {
+      desk: PublicKey;
+      consignment: PublicKey;
+      beneficiary: PublicKey;
+      tokenAmount: anchor.BN;
+      discountBps: number;
+      currency: number;
+      lockupSecs: anchor.BN;
+      agentCommissionBps: number;
+      expiresAt: anchor.BN;
+      nonce: anchor.BN;
+    };
+
+    const u16 = (n: number) => { const b = Buffer.alloc(2); b.writeUInt16LE(n); return b; };
+
+    // Borsh encoding of SignedQuote - the exact bytes the agent signs
+    const encodeQuote = (q: Quote) => Buffer.concat([
+      q.desk.toBuffer(),
+      q.consignment.toBuffer(),
+      q.beneficiary.toBuffer(),
+      q.tokenAmount.toArrayLike(Buffer, "le", 8),
+      u16(q.discountBps),
+      Buffer.from([q.currency]),
+      q.lockupSecs.toTwos(64).toArrayLike(Buffer, "le", 8),
+      u16(q.agentCommissionBps),
+      q.expiresAt.toTwos(64).toArrayLike(Buffer, "le", 8),
+      q.nonce.toArrayLike(Buffer, "le", 8),
+    ]);
+
+    const makeQuote = (consignment: Keypair, expiresInSecs = 600): Quote => ({
+      desk: desk.publicKey,
+      consignment: consignment.publicKey,
+      beneficiary: buyer.publicKey,
+      tokenAmount: TOKENS(1000),
+      discountBps: 500,
+      currency: 1, // USDC
+      lockupSecs: new anchor.BN(0),
+      agentCommissionBps: 50,
+      expiresAt: new anchor.BN(Math.floor(Date.now() / 1000) + expiresInSecs),
+      nonce: new anchor.BN(Math.floor(Math.random() * 1e12)),
+    });
+
+    const redeem = (quote: Quote, consignment: Keypair, offer: Keypair, preInstructions: anchor.web3.TransactionInstruction[]) => {
+      const usedQuote = PublicKey.findProgramAddressSync(
+        [Buffer.from("quote"), desk.publicKey.toBuffer(), quote.nonce.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+      return program.methods
+        .createOfferFromSignedQuote(quote)
+        .accounts({
+          desk: desk.publicKey,
+          consignment: consignment.publicKey,
+          tokenRegistry,
+          beneficiary: buyer.publicKey,
+          offer: offer.publicKey,
+          usedQuote,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .preInstructions(preInstructions)
+        .signers([buyer, offer])
+        .rpc();
+    };
+
+    const signBy = (signer: Keypair, quote: Quote) =>
+      Ed25519Program.createInstructionWithPrivateKey({ privateKey: signer.secretKey, message: encodeQuote(quote) });
+
+    it("should create an approved offer from an agent-signed quote", async () => {
+      const consignment = await createConsignment();
+      const quote = makeQuote(consignment);
+      const offer = Keypair.generate();
+
+      await redeem(quote, consignment, offer, [signBy(agent, quote)]);
+
+      const offerAccount = await program.account.offer.fetch(offer.publicKey);
+      assert.isTrue(offerAccount.approved);
+      assert.equal(offerAccount.tokenAmount.toString(), TOKENS(1000).toString());
+      assert.equal(offerAccount.discountBps, 500);
+    });
+
+    it("should REJECT a quote signed by someone other than the agent", async () => {
+      const consignment = await createConsignment();
+      const quote = makeQuote(consignment);
+
+      await expectRejectedWith(
+        redeem(quote, consignment, Keypair.generate(), [signBy(buyer, quote)]),
+        "InvalidSignature"
+      );
+    });
+
+    it("should REJECT a quote whose terms differ from the signed message", async () => {
+      const consignment = await createConsignment();
+      const signed = makeQuote(consignment);
+      const tampered = { ...signed, discountBps: 1000 };
+
+      await expectRejectedWith(
+        redeem(tampered, consignment, Keypair.generate(), [signBy(agent, signed)]),
+        "InvalidSignature"
+      );
+    });
+
+    it("should REJECT a quote without an Ed25519 instruction", async () => {
+      const consignment = await createConsignment();
+      const quote = makeQuote(consignment);
+
+      await expectRejectedWith(
+        redeem(quote, consignment, Keypair.generate(), []),
+        "InvalidSignature"
+      );
+    });
+
+    it("should REJECT an expired quote", async () => {
+      const consignment = await createConsignment();
+      const quote = makeQuote(consignment, -60);
+
+      await expectRejectedWith(
+        redeem(quote, consignment, Keypair.generate(), [signBy(agent, quote)]),
+        "Expired"
+      );
+    });
+
+    it("should REJECT redeeming the same quote twice", async () => {
+      const consignment = await createConsignment();
+      const quote = makeQuote(consignment);
+      await redeem(quote, consignment, Keypair.generate(), [signBy(agent, quote)]);
+
+      await expectRejectedWith(
+        redeem(quote, consignment, Keypair.generate(), [signBy(agent, quote)]),
+        "already in use"
+      );
+    });
+  });
+
+  // =====================================================
+  // ONE-SHOT P2P PURCHASE
+  // =====================================================
+  describe("Buy From Consignment", () => {
+    it("should create, approve and pay for a P2P offer in one instruction", async () => {
+      const consignment = await createConsignment({ negotiable: false });
+      const buyerUsdcBefore = (await getAccount(provider.connection, buyerUsdcAta)).amount;
+
+      const offer = await buy(consignment, TOKENS(1000));
+
+      const offerAccount = await program.account.offer.fetch(offer.publicKey);
+      assert.isTrue(offerAccount.approved);
+      assert.isTrue(offerAccount.paid);
+      assert.equal(offerAccount.discountBps, 500);
+      const buyerUsdcAfter = (await getAccount(provider.connection, buyerUsdcAta)).amount;
+      assert.equal((buyerUsdcBefore - buyerUsdcAfter).toString(), offerAccount.amountPaid.toString());
+      const after = await program.account.consignment.fetch(consignment.publicKey);
+      assert.equal(after.remainingAmount.toString(), TOKENS(99_000).toString());
+    });
+
+    it("should REJECT a purchase costing more than max_payment", async () => {
+      const consignment = await createConsignment({ negotiable: false });
+
+      await expectRejectedWith(buy(consignment, TOKENS(1000), new anchor.BN(1e6)), "MaxPaymentExceeded");
+    });
+
+    it("should REJECT a one-shot purchase from a negotiable consignment", async () => {
+      const consignment = await createConsignment();
+
+      await expectRejectedWith(buy(consignment, TOKENS(1000)), "NegotiableConsignment");
+    });
+
+    it("should REJECT paying in a currency the consignment does not accept", async () => {
+      const solOnly = await createConsignment({ negotiable: false, acceptedCurrencies: 1 });
+      await expectRejectedWith(buy(solOnly, TOKENS(1000)), "CurrencyNotAccepted");
+
+      const negotiableSolOnly = await createConsignment({ acceptedCurrencies: 1 });
+      await expectRejectedWith(requestOffer(negotiableSolOnly), "CurrencyNotAccepted");
+    });
+  });
+
+  // =====================================================
+  // BATCH CLAIM
+  // =====================================================
+  describe("Batch Claim", () => {
+    const batchClaim = (remainingAccounts: { pubkey: PublicKey; isWritable: boolean; isSigner: boolean }[]) =>
+      program.methods
+        .batchClaim()
+        .accounts({
+          desk: desk.publicKey,
+          deskSigner: desk.publicKey,
+          tokenMint,
+          deskTokenTreasury,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(remainingAccounts)
+        .signers([desk])
+        .rpc();
+
+    it("should deliver every matured offer in the batch", async () => {
+      const consignment = await createConsignment({ negotiable: false });
+      const offers = [await buy(consignment, TOKENS(1000)), await buy(consignment, TOKENS(2000))];
+      const buyerTokenAta = (await getOrCreateAssociatedTokenAccount(
+        provider.connection, buyer, tokenMint, buyer.publicKey
+      )).address;
+
+      await batchClaim(offers.flatMap(offer => [
+        { pubkey: offer.publicKey, isWritable: true, isSigner: false },
+        { pubkey: buyerTokenAta, isWritable: true, isSigner: false },
+      ]));
+
+      for (const offer of offers) {
+        assert.isTrue((await program.account.offer.fetch(offer.publicKey)).fulfilled);
+      }
+      assert.equal((await getAccount(provider.connection, buyerTokenAta)).amount.toString(), TOKENS(3000).toString());
+    });
+
+    it("should REJECT an unpaired account list", async () => {
+      const consignment = await createConsignment({ negotiable: false });
+      const offer = await buy(consignment, TOKENS(1000));
+
+      await expectRejectedWith(
+        batchClaim([{ pubkey: offer.publicKey, isWritable: true, isSigner: false }]),
+        "AmountRange"
+      );
+    });
+  });
+
+  // =====================================================
+  // OFFER CLEANUP
+  // =====================================================
+  describe("Offer Cleanup", () => {
+    it("should close a claimed offer and return its rent to the payer", async () => {
+      const consignment = await createConsignment({ negotiable: false });
+      const offer = await buy(consignment, TOKENS(1000));
+      const buyerTokenAta = (await getOrCreateAssociatedTokenAccount(
+        provider.connection, buyer, tokenMint, buyer.publicKey
+      )).address;
+      await program.methods
+        .batchClaim()
+        .accounts({ desk: desk.publicKey, deskSigner: desk.publicKey, tokenMint, deskTokenTreasury, tokenProgram: TOKEN_PROGRAM_ID })
+        .remainingAccounts([
+          { pubkey: offer.publicKey, isWritable: true, isSigner: false },
+          { pubkey: buyerTokenAta, isWritable: true, isSigner: false },
+        ])
+        .signers([desk])
+        .rpc();
+
+      const rentBefore = await provider.connection.getBalance(buyer.publicKey);
+      await program.methods
+        .cleanupOffer()
+        .accounts({ desk: desk.publicKey, offer: offer.publicKey, rentRecipient: buyer.publicKey, caller: agent.publicKey })
+        .signers([agent])
+        .rpc();
+
+      assert.isNull(await provider.connection.getAccountInfo(offer.publicKey));
+      assert.isAbove(await provider.connection.getBalance(buyer.publicKey), rentBefore);
+    });
+
+    it("should REJECT cleaning up an unpaid offer before it expires", async () => {
+      const consignment = await createConsignment();
+      const offer = await requestOffer(consignment);
+
+      await expectRejectedWith(
+        program.methods
+          .cleanupOffer()
+          .accounts({
+            desk: desk.publicKey,
+            offer: offer.publicKey,
+            consignment: consignment.publicKey,
+            rentRecipient: buyer.publicKey,
+            caller: agent.publicKey,
+          })
+          .signers([agent])
+          .rpc(),
+        "NotExpired"
+      );
+    });
+  });
+
+  // =====================================================
+  // DUTCH AUCTION
+  // =====================================================
+  describe("Dutch Auction", () => {
+    it("should price a P2P purchase on the current point of the discount ramp", async () => {
+      const consignment = await createConsignment({ negotiable: false });
+      const now = Math.floor(Date.now() / 1000);
+      await program.methods
+        .setDutchAuction(await consignmentId(consignment), new anchor.BN(now - 3600), new anchor.BN(now + 3600))
+        .accounts(updateAccounts(consignment))
+        .signers([owner])
+        .rpc();
+
+      const offer = await buy(consignment, TOKENS(1000));
+
+      // Halfway between min_discount_bps (100) and max_discount_bps (1000)
+      const { discountBps } = await program.account.offer.fetch(offer.publicKey);
+      assert.isAtLeast(discountBps, 450);
+      assert.isAtMost(discountBps, 650);
+    });
+
+    it("should REJECT a Dutch auction on a negotiable consignment", async () => {
+      const consignment = await createConsignment();
+      const now = Math.floor(Date.now() / 1000);
+
+      await expectRejectedWith(
+        program.methods
+          .setDutchAuction(await consignmentId(consignment), new anchor.BN(now), new anchor.BN(now + 3600))
+          .accounts(updateAccounts(consignment))
+          .signers([owner])
+          .rpc(),
+        "NegotiableConsignment"
+      );
+    });
+  });
+
+  // =====================================================
+  // COUNTER-OFFERS
+  // =====================================================
+  describe("Counter-Offers", () => {
+    const counter = (offer: Keypair, consignment: Keypair, proposer: Keypair, tokenAmount: anchor.BN, discountBps: number) =>
+      program.methods
+        .counterOffer(tokenAmount, discountBps, new anchor.BN(0))
+        .accounts({ desk: desk.publicKey, offer: offer.publicKey, consignment: consignment.publicKey, proposer: proposer.publicKey })
+        .signers([proposer])
+        .rpc();
+
+    const accept = (offer: Keypair, consignment: Keypair) =>
+      program.methods
+        .respondToCounter(true)
+        .accounts({
+          desk: desk.publicKey,
+          offer: offer.publicKey,
+          consignment: consignment.publicKey,
+          tokenRegistry,
+          beneficiary: buyer.publicKey,
+        })
+        .signers([buyer])
+        .rpc();
+
+    it("should re-quote the offer when the beneficiary accepts a counter", async () => {
+      const consignment = await createConsignment();
+      const offer = await requestOffer(consignment, TOKENS(1000), 800);
+      const createdAt = (await program.account.offer.fetch(offer.publicKey)).createdAt.toString();
+
+      await counter(offer, consignment, agent, TOKENS(2000), 300);
+      await accept(offer, consignment);
+
+      const offerAccount = await program.account.offer.fetch(offer.publicKey);
+      // The counter does not extend the quote expiry
+      assert.equal(offerAccount.createdAt.toString(), createdAt);
+      assert.isFalse(offerAccount.counterPending);
+      assert.equal(offerAccount.counterRound, 1);
+      assert.equal(offerAccount.tokenAmount.toString(), TOKENS(2000).toString());
+      assert.equal(offerAccount.discountBps, 300);
+      const after = await program.account.consignment.fetch(consignment.publicKey);
+      assert.equal(after.remainingAmount.toString(), TOKENS(98_000).toString());
+    });
+
+    it("should REJECT a counter from someone who is not the agent, an approver or the consigner", async () => {
+      const consignment = await createConsignment();
+      const offer = await requestOffer(consignment);
+
+      await expectRejectedWith(counter(offer, consignment, buyer, TOKENS(1000), 300), "NotApprover");
+    });
+
+    it("should re-list inventory freed by a smaller accepted counter", async () => {
+      const consignment = await createConsignment({ amount: TOKENS(5000) });
+      const offer = await requestOffer(consignment, TOKENS(5000), 800);
+      assert.isFalse((await program.account.consignment.fetch(consignment.publicKey)).isActive);
+
+      await counter(offer, consignment, agent, TOKENS(2000), 300);
+      await accept(offer, consignment);
+
+      const after = await program.account.consignment.fetch(consignment.publicKey);
+      assert.equal(after.remainingAmount.toString(), TOKENS(3000).toString());
+      assert.isTrue(after.isActive);
+    });
+
+    it("should REJECT counters once the consignment has expired", async () => {
+      const consignment = await createConsignment();
+      const offer = await requestOffer(consignment, TOKENS(1000), 800);
+      await counter(offer, consignment, agent, TOKENS(2000), 300);
+
+      await program.methods
+        .setConsignmentExpiry(await consignmentId(consignment), new anchor.BN(Math.floor(Date.now() / 1000) + 2))
+        .accounts(updateAccounts(consignment))
+        .signers([owner])
+        .rpc();
+      await new Promise(r => setTimeout(r, 4000));
+
+      await expectRejectedWith(counter(offer, consignment, agent, TOKENS(3000), 300), "ConsignmentExpired");
+      await expectRejectedWith(accept(offer, consignment), "ConsignmentExpired");
+    });
+  });
+
+  // =====================================================
+  // DISCOUNT TIERS
+  // =====================================================
+  describe("Discount Tiers", () => {
+    const setTiers = async (consignment: Keypair, tiers: { minSize: anchor.BN; minDiscountBps: number; maxDiscountBps: number }[]) =>
+      program.methods
+        .setDiscountTiers(await consignmentId(consignment), { tokenAmount: {} }, tiers)
+        .accounts(updateAccounts(consignment))
+        .signers([owner])
+        .rpc();
+
+    it("should apply the tier matching the order size", async () => {
+      const consignment = await createConsignment({ negotiable: false });
+      await setTiers(consignment, [
+        { minSize: TOKENS(1000), minDiscountBps: 200, maxDiscountBps: 200 },
+        { minSize: TOKENS(10_000), minDiscountBps: 600, maxDiscountBps: 600 },
+      ]);
+
+      const small = await buy(consignment, TOKENS(2000));
+      const large = await buy(consignment, TOKENS(10_000));
+
+      assert.equal((await program.account.offer.fetch(small.publicKey)).discountBps, 200);
+      assert.equal((await program.account.offer.fetch(large.publicKey)).discountBps, 600);
+    });
+
+    it("should REJECT a P2P tier with a discount band", async () => {
+      const consignment = await createConsignment({ negotiable: false });
+
+      await expectRejectedWith(
+        setTiers(consignment, [{ minSize: TOKENS(1000), minDiscountBps: 200, maxDiscountBps: 400 }]),
+        "Discount"
+      );
+    });
+  });
+
+  // =====================================================
+  // FIXED PRICE
+  // =====================================================
+  describe("Fixed Price", () => {
+    it("should price offers at the consigner's fixed price instead of the registry price", async () => {
+      const consignment = await createConsignment({ negotiable: false });
+      await program.methods
+        .setConsignmentFixedPrice(await consignmentId(consignment), new anchor.BN(20 * 1e8)) // $20 vs $10 registry
+        .accounts(updateAccounts(consignment))
+        .signers([owner])
+        .rpc();
+
+      const offer = await buy(consignment, TOKENS(1000));
+
+      const offerAccount = await program.account.offer.fetch(offer.publicKey);
+      assert.equal(offerAccount.priceUsdPerToken8D.toString(), (20 * 1e8).toString());
+    });
+
+    it("should REJECT a fixed price on a withdrawn consignment", async () => {
+      const consignment = await createConsignment({ negotiable: false });
+      const id = await consignmentId(consignment);
+      await program.methods
+        .withdrawConsignment(id)
+        .accounts(withdrawAccounts(consignment))
+        .signers([owner, desk])
+        .rpc();
+
+      await expectRejectedWith(
+        program.methods
+          .setConsignmentFixedPrice(id, new anchor.BN(20 * 1e8))
+          .accounts(updateAccounts(consignment))
+          .signers([owner])
+          .rpc(),
+        "BadState"
+      );
+    });
+  });
+
+  // =====================================================
+  // ORDER QUOTES
+  // =====================================================
+  describe("Order Quotes", () => {
+    const quoteOrder = async (consignment: Keypair, tokenAmount: anchor.BN, discountBps: number, currency: number) =>
+      program.methods
+        .quoteOrder(await consignmentId(consignment), tokenAmount, discountBps, currency, new anchor.BN(0), 0, null)
+        .accounts({ desk: desk.publicKey, consignment: consignment.publicKey, tokenRegistry })
+        .view();
+
+    it("should quote exactly what a tiered, fixed-price purchase then pays", async () => {
+      const consignment = await createConsignment({ negotiable: false });
+      const id = await consignmentId(consignment);
+      await program.methods
+        .setConsignmentFixedPrice(id, new anchor.BN(20 * 1e8))
+        .accounts(updateAccounts(consignment))
+        .signers([owner])
+        .rpc();
+      await program.methods
+        .setDiscountTiers(id, { tokenAmount: {} }, [
+          { minSize: TOKENS(1000), minDiscountBps: 200, maxDiscountBps: 200 },
+          { minSize: TOKENS(5000), minDiscountBps: 700, maxDiscountBps: 700 },
+        ])
+        .accounts(updateAccounts(consignment))
+        .signers([owner])
+        .rpc();
+
+      // P2P buyers pass 0 to accept the tier discount
+      const quote = await quoteOrder(consignment, TOKENS(5000), 0, 1);
+      const offer = await buy(consignment, TOKENS(5000));
+
+      const offerAccount = await program.account.offer.fetch(offer.publicKey);
+      assert.equal(offerAccount.discountBps, 700);
+      assert.equal(quote.paymentAmount.toString(), offerAccount.amountPaid.toString());
+      assert.equal(quote.discountedUsd8D.toString(), (93_000 * 1e8).toString()); // 5000 tokens at $20 less 7%
+    });
+
+    it("should REJECT quoting a currency the consignment does not accept", async () => {
+      const consignment = await createConsignment({ negotiable: false, acceptedCurrencies: 2 }); // USDC only
+
+      await expectRejectedWith(quoteOrder(consignment, TOKENS(1000), 500, 0), "CurrencyNotAccepted");
+    });
+  });
+
+  // =====================================================
+  // SEALED-BID AUCTION
+  // =====================================================
+  describe("Sealed-Bid Auction", () => {
+    const USDC = (n: number) => new anchor.BN(n).mul(new anchor.BN(1e6));
+    const sleepUntil = async (unixSecs: number) => {
+      const ms = (unixSecs + 1) * 1000 - Date.now();
+      if (ms > 0) await new Promise(r => setTimeout(r, ms));
+    };
+
+    const auctionPda = (consignment: Keypair) =>
+      PublicKey.findProgramAddressSync([Buffer.from("auction"), consignment.publicKey.toBuffer()], program.programId)[0];
+    const sealedBidPda = (auction: PublicKey, bidder: PublicKey) =>
+      PublicKey.findProgramAddressSync([Buffer.from("sealed_bid"), auction.toBuffer(), bidder.toBuffer()], program.programId)[0];
+
+    const fundUsdc = async (kp: Keypair, amount: bigint) => {
+      const ata = (await getOrCreateAssociatedTokenAccount(provider.connection, owner, usdcMint, kp.publicKey)).address;
+      if (amount > 0n) await mintTo(provider.connection, owner, usdcMint, ata, owner, amount);
+      return ata;
+    };
+
+    // Opens an auction on a fresh 100k token consignment and returns its timeline
+    const createAuction = async (opts: { depositUsdc?: number; depositBps?: number; claimWindowSecs?: number } = {}) => {
+      const consignment = await createConsignment();
+      const auction = auctionPda(consignment);
+      const now = Math.floor(Date.now() / 1000);
+      const commitEndAt = now + 5;
+      const revealEndAt = now + 10;
+      await program.methods
+        .createAuction(
+          await consignmentId(consignment),
+          new anchor.BN(commitEndAt),
+          new anchor.BN(revealEndAt),
+          USDC(opts.depositUsdc ?? 1000),
+          opts.depositBps ?? 100, // deposits cover 1% of the bid
+          new anchor.BN(opts.claimWindowSecs ?? 3600)
+        )
+        .accounts({ desk: desk.publicKey, consignment: consignment.publicKey, consigner: owner.publicKey })
+        .signers([owner])
+        .rpc();
+      return { consignment, auction, commitEndAt, revealEndAt };
+    };
+
+    const commitment = (bidder: PublicKey, discountBps: number, tokenAmount: anchor.BN, salt: Buffer) => {
+      const discount = Buffer.alloc(2);
+      discount.writeUInt16LE(discountBps);
+      return Array.from(keccak_256(Buffer.concat([discount, tokenAmount.toArrayLike(Buffer, "le", 8), salt, bidder.toBuffer()])));
+    };
+
+    const commit = (auction: PublicKey, bidder: Keypair, bidderUsdcAta: PublicKey, hash: number[], deposit: anchor.BN) =>
+      program.methods
+        .commitBid(hash, deposit)
+        .accounts({
+          desk: desk.publicKey,
+          auction,
+          bidder: bidder.publicKey,
+          usdcMint,
+          bidderUsdcAta,
+          deskUsdcTreasury,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([bidder])
+        .rpc();
+
+    const reveal = (auction: PublicKey, consignment: Keypair, bidder: Keypair, discountBps: number, tokenAmount: anchor.BN, salt: Buffer) =>
+      program.methods
+        .revealBid(discountBps, tokenAmount, Array.from(salt))
+        .accounts({
+          desk: desk.publicKey,
+          auction,
+          consignment: consignment.publicKey,
+          tokenRegistry,
+          sealedBid: sealedBidPda(auction, bidder.publicKey),
+          bidder: bidder.publicKey,
+        })
+        .signers([bidder])
+        .rpc();
+
+    const settle = (auction: PublicKey, consignment: Keypair) =>
+      program.methods
+        .settleAuction()
+        .accounts({ desk: desk.publicKey, auction, consignment: consignment.publicKey, tokenRegistry })
+        .rpc();
+
+    const claim = async (auction: PublicKey, consignment: Keypair, bidder: Keypair) => {
+      const offer = Keypair.generate();
+      await program.methods
+        .claimAuctionOffer()
+        .accounts({
+          desk: desk.publicKey,
+          auction,
+          consignment: consignment.publicKey,
+          tokenRegistry,
+          sealedBid: sealedBidPda(auction, bidder.publicKey),
+          bidder: bidder.publicKey,
+          offer: offer.publicKey,
+        })
+        .signers([bidder, offer])
+        .rpc();
+      return offer;
+    };
+
+    const release = (auction: PublicKey, consignment: Keypair, bidder: PublicKey, recipientUsdcAta: PublicKey, offer: PublicKey | null = null) =>
+      program.methods
+        .releaseBidDeposit()
+        .accounts({
+          desk: desk.publicKey,
+          deskSigner: desk.publicKey,
+          auction,
+          consignment: consignment.publicKey,
+          sealedBid: sealedBidPda(auction, bidder),
+          bidder,
+          offer,
+          usdcMint,
+          deskUsdcTreasury,
+          recipientUsdcAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([desk])
+        .rpc();
+
+    const usdcBalance = async (ata: PublicKey) => (await getAccount(provider.connection, ata)).amount;
+
+    it("should allocate by discount at the settlement price and hold a winner's deposit until payment", async () => {
+      const { consignment, auction, commitEndAt, revealEndAt } = await createAuction();
+      const lurker = Keypair.generate();
+      await airdrop(lurker.publicKey, LAMPORTS_PER_SOL);
+      const agentUsdcAta = await fundUsdc(agent, 10_000n * 10n ** 6n);
+      const lurkerUsdcAta = await fundUsdc(lurker, 10_000n * 10n ** 6n);
+      const ownerUsdcAta = await fundUsdc(owner, 0n);
+
+      const buyerSalt = Buffer.alloc(32, 1);
+      const agentSalt = Buffer.alloc(32, 2);
+      await commit(auction, buyer, buyerUsdcAta, commitment(buyer.publicKey, 200, TOKENS(30_000), buyerSalt), USDC(3000));
+      await commit(auction, agent, agentUsdcAta, commitment(agent.publicKey, 500, TOKENS(60_000), agentSalt), USDC(6000));
+      await commit(auction, lurker, lurkerUsdcAta, commitment(lurker.publicKey, 100, TOKENS(10_000), Buffer.alloc(32, 3)), USDC(1000));
+
+      await sleepUntil(commitEndAt);
+      // Revealed out of discount order; the auction keeps them sorted
+      await reveal(auction, consignment, agent, 500, TOKENS(60_000), agentSalt);
+      await reveal(auction, consignment, buyer, 200, TOKENS(30_000), buyerSalt);
+
+      await sleepUntil(revealEndAt);
+      await settle(auction, consignment);
+
+      const settled = await program.account.auction.fetch(auction);
+      assert.isTrue(settled.settled);
+      assert.equal(settled.clearingPrice8D.toString(), (10 * 1e8).toString());
+      assert.deepEqual(settled.bids.map(b => b.bidder.toBase58()), [buyer.publicKey.toBase58(), agent.publicKey.toBase58()]);
+      assert.equal(settled.bids[0].allocatedAmount.toString(), TOKENS(30_000).toString());
+      assert.equal(settled.bids[1].allocatedAmount.toString(), TOKENS(50_000).toString()); // capped at max deal
+      const afterSettle = await program.account.consignment.fetch(consignment.publicKey);
+      assert.equal(afterSettle.remainingAmount.toString(), TOKENS(20_000).toString());
+      assert.isFalse(afterSettle.auctionLive);
+
+      // A later price move does not reprice the winning offers
+      await program.methods
+        .setManualTokenPrice(new anchor.BN(12 * 1e8))
+        .accounts({ tokenRegistry, desk: desk.publicKey, owner: owner.publicKey })
+        .signers([owner])
+        .rpc();
+      const offer = await claim(auction, consignment, buyer);
+      const offerAccount = await program.account.offer.fetch(offer.publicKey);
+      assert.isTrue(offerAccount.approved);
+      assert.equal(offerAccount.priceUsdPerToken8D.toString(), (10 * 1e8).toString());
+      assert.equal(offerAccount.tokenAmount.toString(), TOKENS(30_000).toString());
+      assert.equal(offerAccount.discountBps, 200);
+      assert.equal(offerAccount.auctionBid.toBase58(), sealedBidPda(auction, buyer.publicKey).toBase58());
+
+      // The winner's deposit stays held while the offer is open
+      await expectRejectedWith(release(auction, consignment, buyer.publicKey, buyerUsdcAta, offer.publicKey), "NotExpired");
+
+      await program.methods
+        .fulfillOfferUsdc(new anchor.BN(offerAccount.id.toString()))
+        .accounts({
+          desk: desk.publicKey,
+          offer: offer.publicKey,
+          usdcMint,
+          deskTokenTreasury,
+          deskUsdcTreasury,
+          payerUsdcAta: buyerUsdcAta,
+          deskSigner: desk.publicKey,
+          payer: buyer.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([buyer, desk])
+        .rpc();
+
+      const buyerBefore = await usdcBalance(buyerUsdcAta);
+      await release(auction, consignment, buyer.publicKey, buyerUsdcAta, offer.publicKey);
+      assert.equal((await usdcBalance(buyerUsdcAta)) - buyerBefore, 3000n * 10n ** 6n);
+      assert.isNull(await provider.connection.getAccountInfo(sealedBidPda(auction, buyer.publicKey)));
+      assert.equal((await program.account.offer.fetch(offer.publicKey)).auctionBid.toBase58(), PublicKey.default.toBase58());
+
+      // Unrevealed bids forfeit to the consigner
+      await release(auction, consignment, lurker.publicKey, ownerUsdcAta);
+      assert.equal(await usdcBalance(ownerUsdcAta), 1000n * 10n ** 6n);
+    });
+
+    it("should return an unclaimed allocation to the consignment after the claim window", async () => {
+      const { consignment, auction, commitEndAt, revealEndAt } = await createAuction({ claimWindowSecs: 3 });
+      const ownerUsdcAta = await fundUsdc(owner, 0n);
+      const salt = Buffer.alloc(32, 4);
+      await commit(auction, buyer, buyerUsdcAta, commitment(buyer.publicKey, 300, TOKENS(40_000), salt), USDC(4000));
+      await sleepUntil(commitEndAt);
+      await reveal(auction, consignment, buyer, 300, TOKENS(40_000), salt);
+      await sleepUntil(revealEndAt);
+      await settle(auction, consignment);
+
+      await expectRejectedWith(release(auction, consignment, buyer.publicKey, ownerUsdcAta), "AuctionPhase");
+
+      const { claimEndAt } = await program.account.auction.fetch(auction);
+      await sleepUntil(claimEndAt.toNumber());
+      await expectRejectedWith(claim(auction, consignment, buyer), "AuctionPhase");
+
+      await release(auction, consignment, buyer.publicKey, ownerUsdcAta);
+      assert.equal(await usdcBalance(ownerUsdcAta), 4000n * 10n ** 6n);
+      const after = await program.account.consignment.fetch(consignment.publicKey);
+      assert.equal(after.remainingAmount.toString(), TOKENS(100_000).toString()); // 60k returned at settlement, 40k now
+    });
+
+    it("should REJECT a zero deposit, a deposit under the minimum and a reveal the deposit does not cover", async () => {
+      await expectRejectedWith(createAuction({ depositUsdc: 0 }), "AmountRange");
+
+      const { consignment, auction, commitEndAt } = await createAuction();
+      const salt = Buffer.alloc(32, 5);
+      const hash = commitment(buyer.publicKey, 0, TOKENS(50_000), salt);
+      await expectRejectedWith(commit(auction, buyer, buyerUsdcAta, hash, USDC(999)), "DepositTooLow");
+
+      // 50k tokens at $10 needs a $5,000 deposit at 1%
+      await commit(auction, buyer, buyerUsdcAta, hash, USDC(1000));
+      await sleepUntil(commitEndAt);
+      await expectRejectedWith(reveal(auction, consignment, buyer, 0, TOKENS(50_000), salt), "DepositTooLow");
+    });
+  });
+
+  // =====================================================
+  // BUY-SIDE BIDS
+  // =====================================================
+  describe("Buy-Side Bids", () => {
+    // 5000 token bid at 5% off with 100k USDC escrowed
+    const createBid = async () => {
+      const bid = Keypair.generate();
+      await program.methods
+        .createBid(TOKENS(5000), 500, new anchor.BN(0), new anchor.BN(100_000 * 1e6), new anchor.BN(0))
+        .accounts({
+          desk: desk.publicKey,
+          tokenRegistry,
+          bid: bid.publicKey,
+          buyer: buyer.publicKey,
+          usdcMint,
+          buyerUsdcAta,
+          deskUsdcTreasury,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([buyer, bid])
+        .rpc();
+      return bid;
+    };
+
+    const fillBid = async (bid: Keypair, tokenAmount: anchor.BN, consignment: Keypair | null = null) => {
+      const offer = Keypair.generate();
+      const ownerUsdcAta = (await getOrCreateAssociatedTokenAccount(provider.connection, owner, usdcMint, owner.publicKey)).address;
+      await program.methods
+        .fillBid(tokenAmount)
+        .accounts({
+          desk: desk.publicKey,
+          deskSigner: desk.publicKey,
+          bid: bid.publicKey,
+          tokenRegistry,
+          offer: offer.publicKey,
+          seller: owner.publicKey,
+          consignment: consignment ? consignment.publicKey : null,
+          sellerTokenAta: consignment ? null : ownerTokenAta,
+          tokenMint,
+          deskTokenTreasury,
+          usdcMint,
+          deskUsdcTreasury,
+          sellerUsdcAta: ownerUsdcAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([owner, desk, offer])
+        .rpc();
+      return { offer, ownerUsdcAta };
+    };
+
+    it("should fill a bid from the seller's wallet and refuse to emergency refund the paid offer", async () => {
+      const bid = await createBid();
+      const { offer, ownerUsdcAta } = await fillBid(bid, TOKENS(2000));
+
+      const offerAccount = await program.account.offer.fetch(offer.publicKey);
+      assert.isTrue(offerAccount.paid);
+      assert.isTrue(offerAccount.sellerPaid);
+      assert.equal(offerAccount.amountPaid.toString(), (19_000 * 1e6).toString()); // 2000 tokens at $10 less 5%
+      const sellerNet = offerAccount.amountPaid.sub(offerAccount.commissionAccrued).sub(offerAccount.protocolFeeAccrued);
+      assert.equal((await getAccount(provider.connection, ownerUsdcAta)).amount.toString(), sellerNet.toString());
+      const bidAccount = await program.account.bid.fetch(bid.publicKey);
+      assert.equal(bidAccount.filledAmount.toString(), TOKENS(2000).toString());
+      assert.equal(bidAccount.escrowRemaining.toString(), (81_000 * 1e6).toString());
+
+      // The seller already holds the payment, so a refund would pay it out twice
+      await program.methods
+        .setEmergencyRefund(true, new anchor.BN(1))
+        .accounts({ desk: desk.publicKey, owner: owner.publicKey })
+        .signers([owner])
+        .rpc();
+      await new Promise(r => setTimeout(r, 2000));
+      await expectRejectedWith(
+        program.methods
+          .emergencyRefundUsdc(offerAccount.id)
+          .accounts({
+            desk: desk.publicKey,
+            deskSigner: desk.publicKey,
+            offer: offer.publicKey,
+            usdcMint,
+            caller: buyer.publicKey,
+            deskUsdcTreasury,
+            payerUsdcRefund: buyerUsdcAta,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([buyer, desk])
+          .rpc(),
+        "NotRefundable"
+      );
+    });
+
+    it("should hold consignment fills to the consignment's currencies, deal size and reserve", async () => {
+      const bid = await createBid();
+
+      const solOnly = await createConsignment({ acceptedCurrencies: 1 });
+      await expectRejectedWith(fillBid(bid, TOKENS(1000), solOnly), "CurrencyNotAccepted");
+
+      const consignment = await createConsignment(); // 1k - 50k token deals
+      await expectRejectedWith(fillBid(bid, TOKENS(500), consignment), "AmountRange");
+
+      await program.methods
+        .setConsignmentReserve(await consignmentId(consignment), new anchor.BN(960_000_000))
+        .accounts(updateAccounts(consignment))
+        .signers([owner])
+        .rpc();
+      await expectRejectedWith(fillBid(bid, TOKENS(1000), consignment), "BelowReservePrice"); // $9.50 net
+
+      await program.methods
+        .setConsignmentReserve(await consignmentId(consignment), new anchor.BN(950_000_000))
+        .accounts(updateAccounts(consignment))
+        .signers([owner])
+        .rpc();
+      const { offer } = await fillBid(bid, TOKENS(1000), consignment);
+      const offerAccount = await program.account.offer.fetch(offer.publicKey);
+      assert.equal(offerAccount.consignmentId.toString(), (await consignmentId(consignment)).toString());
+      const after = await program.account.consignment.fetch(consignment.publicKey);
+      assert.equal(after.remainingAmount.toString(), TOKENS(99_000).toString());
+    });
+
+    it("should REJECT a fill under the desk minimum", async () => {
+      const bid = await createBid();
+      // 0.05 tokens is $0.475 after the discount, under the $1 minimum
+      await expectRejectedWith(fillBid(bid, new anchor.BN(5e7)), "MinUsd");
+    });
+  });
+
+  // =====================================================
+  // RESERVE PRICE
+  // =====================================================
+  describe("Reserve Price", () => {
+    const setTokenPrice = (price8d: number) =>
+      program.methods
+        .setManualTokenPrice(new anchor.BN(price8d))
+        .accounts({ tokenRegistry, desk: desk.publicKey, owner: owner.publicKey })
+        .signers([owner])
+        .rpc();
+
+    const fulfill = async (offer: Keypair, consignment: Keypair | null) =>
+      program.methods
+        .fulfillOfferUsdc((await program.account.offer.fetch(offer.publicKey)).id)
+        .accounts({
+          desk: desk.publicKey,
+          offer: offer.publicKey,
+          consignment: consignment ? consignment.publicKey : null,
+          tokenRegistry: consignment ? tokenRegistry : null,
+          usdcMint,
+          deskTokenTreasury,
+          deskUsdcTreasury,
+          payerUsdcAta: buyerUsdcAta,
+          deskSigner: desk.publicKey,
+          payer: buyer.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([buyer, desk])
+        .rpc();
+
+    it("should REJECT fulfilling an offer the price has since moved under the reserve", async () => {
+      const consignment = await createConsignment();
+      await program.methods
+        .setConsignmentReserve(await consignmentId(consignment), new anchor.BN(9 * 1e8))
+        .accounts(updateAccounts(consignment))
+        .signers([owner])
+        .rpc();
+
+      // $10 less 5% clears the $9 reserve when the offer is created
+      const offer = await requestOffer(consignment);
+      await program.methods
+        .approveOffer((await program.account.offer.fetch(offer.publicKey)).id)
+        .accounts({ desk: desk.publicKey, offer: offer.publicKey, consignment: consignment.publicKey, approver: agent.publicKey })
+        .signers([agent])
+        .rpc();
+
+      // $9.40 less 5% is $8.93
+      await setTokenPrice(9.4 * 1e8);
+      await expectRejectedWith(fulfill(offer, consignment), "BelowReservePrice");
+      // The current price cannot be skipped by leaving the consignment out
+      await expectRejectedWith(fulfill(offer, null), "BadState");
+
+      await setTokenPrice(10 * 1e8);
+      await fulfill(offer, consignment);
+      assert.isTrue((await program.account.offer.fetch(offer.publicKey)).paid);
+    });
+  });
+});
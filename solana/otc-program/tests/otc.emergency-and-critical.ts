@@ -270,7 +270,8 @@ describe("Emergency Refund and Critical Path Tests", () => {
           false, 500, 0, 0, 0, 0, 0,
           new anchor.BN("10000000000"),
           new anchor.BN("50000000000000"),
-          true, false, 0, new anchor.BN(3600)
+          true, false, 0, new anchor.BN(3600),
+          3 // accepted_currencies: SOL | USDC
         )
         .accounts({
           desk: desk.publicKey,
@@ -297,7 +298,8 @@ describe("Emergency Refund and Critical Path Tests", () => {
         .createOfferFromConsignment(
           new anchor.BN(consignmentAccount.id.toString()),
           new anchor.BN("10000000000"), // 10 tokens
-          500, 1, new anchor.BN(0), 0
+          500, 1, new anchor.BN(0), 0,
+          [] // allowlist_proof
         )
         .accounts({
           desk: desk.publicKey,
@@ -321,7 +323,6 @@ describe("Emergency Refund and Critical Path Tests", () => {
           deskTokenTreasury,
           deskUsdcTreasury,
           payerUsdcAta: buyerUsdcAta,
-          agentUsdcAta: null,
           deskSigner: desk.publicKey,
           payer: buyer.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
@@ -395,6 +396,10 @@ describe("Emergency Refund and Critical Path Tests", () => {
 
       const offerAccount = await program.account.offer.fetch(offer.publicKey);
       const buyerUsdcBefore = (await getAccount(provider.connection, buyerUsdcAta)).amount;
+      const [agentCommission] = PublicKey.findProgramAddressSync(
+        [Buffer.from("commission"), desk.publicKey.toBuffer(), agent.publicKey.toBuffer(), Buffer.from([1])],
+        program.programId
+      );
 
       await program.methods
         .emergencyRefundUsdc(new anchor.BN(offerAccount.id.toString()))
@@ -406,6 +411,7 @@ describe("Emergency Refund and Critical Path Tests", () => {
           caller: buyer.publicKey,
           deskUsdcTreasury,
           payerUsdcRefund: buyerUsdcAta,
+          agentCommission,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([buyer, desk])
@@ -444,7 +450,8 @@ describe("Emergency Refund and Critical Path Tests", () => {
           false, 500, 0, 0, 0, 0, 0,
           new anchor.BN("1000000000"), // 1 token min (small amount)
           new anchor.BN("50000000000000"),
-          true, false, 0, new anchor.BN(3600)
+          true, false, 0, new anchor.BN(3600),
+          3 // accepted_currencies: SOL | USDC
         )
         .accounts({
           desk: desk.publicKey,
@@ -468,7 +475,8 @@ describe("Emergency Refund and Critical Path Tests", () => {
           new anchor.BN(consignmentAccount.id.toString()),
           new anchor.BN("1000000000"), // 1 token = $10 -> 0.1 SOL
           500, 0, // SOL payment
-          new anchor.BN(0), 0
+          new anchor.BN(0), 0,
+          [] // allowlist_proof
         )
         .accounts({
           desk: desk.publicKey,
@@ -489,7 +497,6 @@ describe("Emergency Refund and Critical Path Tests", () => {
           desk: desk.publicKey,
           offer: offer.publicKey,
           deskTokenTreasury,
-          agent: null,
           deskSigner: desk.publicKey,
           payer: buyer.publicKey,
           systemProgram: SystemProgram.programId,
@@ -503,6 +510,13 @@ describe("Emergency Refund and Critical Path Tests", () => {
 
       const offerAccount = await program.account.offer.fetch(offer.publicKey);
       const buyerSolBefore = await provider.connection.getBalance(buyer.publicKey);
+      const [agentCommission] = PublicKey.findProgramAddressSync(
+        [Buffer.from("commission"), desk.publicKey.toBuffer(), agent.publicKey.toBuffer(), Buffer.from([0])],
+        program.programId
+      );
+      // P2P commission was credited to the agent when the offer was paid
+      assert.isTrue(offerAccount.commissionAccrued.toNumber() > 0);
+      const accrualBefore = await program.account.commissionAccrual.fetch(agentCommission);
 
       await program.methods
         .emergencyRefundSol(new anchor.BN(offerAccount.id.toString()))
@@ -512,11 +526,21 @@ describe("Emergency Refund and Critical Path Tests", () => {
           offer: offer.publicKey,
           caller: buyer.publicKey,
           payerRefund: buyer.publicKey,
+          agentCommission,
           systemProgram: SystemProgram.programId,
         })
         .signers([buyer, desk])
         .rpc();
 
+      // The refunded offer's commission is taken back from the agent
+      const accrualAfter = await program.account.commissionAccrual.fetch(agentCommission);
+      assert.equal(
+        accrualBefore.accruedAmount.sub(accrualAfter.accruedAmount).toString(),
+        offerAccount.commissionAccrued.toString()
+      );
+      const deskAfter = await program.account.desk.fetch(desk.publicKey);
+      assert.equal(deskAfter.commissionOwedLamports.toString(), "0");
+
       const buyerSolAfter = await provider.connection.getBalance(buyer.publicKey);
       // Balance should increase (minus tx fee)
       assert.isTrue(buyerSolAfter > buyerSolBefore - 10000);
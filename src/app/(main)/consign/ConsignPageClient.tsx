@@ -80,6 +80,7 @@ const INITIAL_FORM_DATA = {
   isPrivate: false,
   maxPriceVolatilityBps: 1000,
   maxTimeToExecuteSeconds: 1800,
+  acceptedCurrencies: 3, // Solana payment currencies bitmask: 1 = SOL, 2 = USDC
   selectedPoolAddress: "", // User-selected pool for token registration (EVM only)
 };
 
@@ -461,6 +462,7 @@ export default function ConsignPageClient() {
             formData.isPrivate,
             formData.maxPriceVolatilityBps,
             new anchor.BN(formData.maxTimeToExecuteSeconds),
+            formData.acceptedCurrencies,
           )
           .accounts({
             desk: desk,
@@ -531,6 +533,7 @@ export default function ConsignPageClient() {
                 formData.isPrivate,
                 formData.maxPriceVolatilityBps,
                 new anchor.BN(formData.maxTimeToExecuteSeconds),
+                formData.acceptedCurrencies,
               )
               .accounts({
                 desk: desk,
@@ -999,6 +999,7 @@ export default function FlowTestClient() {
           false, // not private
           1000, // volatility
           new anchor.BN(1800), // max time
+          3, // accepts SOL and USDC
         )
         .accounts({
           desk,
@@ -1290,6 +1291,7 @@ export default function FlowTestClient() {
           SOLANA_PAYMENT_CURRENCY, // currency (0 = SOL, 1 = USDC)
           lockupSeconds, // lockup_secs
           SOLANA_AGENT_COMMISSION_BPS, // agent_commission_bps
+          [], // allowlist_proof (public consignment)
         )
         .accounts({
           desk,
@@ -328,6 +328,9 @@ async function handleApproval(request: NextRequest) {
           currency: number;
           id: import("@coral-xyz/anchor").BN;
           tokenMint: SolanaPublicKey;
+          protocolFeeBps: number;
+          referrer: SolanaPublicKey;
+          referrerBps: number;
         }>;
       };
       desk: {
@@ -343,6 +346,7 @@ async function handleApproval(request: NextRequest) {
     type DeskAccountData = {
       usdcMint: SolanaPublicKey;
       agent: SolanaPublicKey;
+      protocolTreasury: SolanaPublicKey;
       solUsdPrice8D: { toNumber: () => number };
     };
     const deskData = (await programAccounts.desk.fetch(desk)) as DeskAccountData;
@@ -512,6 +516,40 @@ async function handleApproval(request: NextRequest) {
       console.log("[Approve API] Prices set on desk");
     }
 
+    // Fee shares are credited to per-recipient accrual PDAs instead of being paid out at fulfillment.
+    // The agent's accrual is opened by the program; protocol and referrer accruals must exist when their share is non-zero.
+    const feeAccrual = (recipient: SolanaPublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("commission"), desk.toBuffer(), recipient.toBuffer(), Buffer.from([offerData.currency])],
+        program.programId,
+      )[0];
+    const feeRecipients = [
+      offerData.protocolFeeBps > 0 ? deskData.protocolTreasury : null,
+      offerData.referrerBps > 0 ? offerData.referrer : null,
+    ];
+    for (const recipient of feeRecipients) {
+      if (recipient && !(await connection.getAccountInfo(feeAccrual(recipient)))) {
+        await program.methods
+          .openFeeAccount(recipient, offerData.currency)
+          .accounts({ desk, payer: payerKeypair.publicKey })
+          .signers([payerKeypair])
+          .rpc();
+      }
+    }
+    const feeAccounts = {
+      agentCommission: feeAccrual(deskData.agent),
+      protocolFeeAccrual: feeRecipients[0] ? feeAccrual(feeRecipients[0]) : null,
+      referrerFeeAccrual: feeRecipients[1] ? feeAccrual(feeRecipients[1]) : null,
+    };
+    // The consignment reserve is re-checked against the current consignment price at fulfillment
+    const reserveAccounts = {
+      consignment,
+      tokenRegistry: PublicKey.findProgramAddressSync(
+        [Buffer.from("registry"), desk.toBuffer(), tokenMint.toBuffer()],
+        program.programId,
+      )[0],
+    };
+
     let fulfillTx: string;
 
     try {
@@ -534,7 +572,8 @@ async function handleApproval(request: NextRequest) {
             desk,
             offer,
             deskTokenTreasury,
-            agent: deskData.agent,
+            ...feeAccounts,
+            ...reserveAccounts,
             deskSigner: deskKeypair.publicKey,
             payer: payerKeypair.publicKey,
             systemProgram: new PublicKey("11111111111111111111111111111111"),
@@ -551,9 +590,6 @@ async function handleApproval(request: NextRequest) {
         payerKeypair.publicKey,
         false,
       );
-      // Agent USDC ATA for commission (optional)
-      const agentUsdcAta = await getAssociatedTokenAddress(usdcMint, deskData.agent, false);
-
       console.log(`[Approve API] Attempting USDC payment with payer: ${payerKeypair.publicKey.toBase58()}`);
       
       // Build signers array - include payer if different from approver
@@ -573,7 +609,8 @@ async function handleApproval(request: NextRequest) {
           deskTokenTreasury,
           deskUsdcTreasury,
           payerUsdcAta,
-          agentUsdcAta,
+          ...feeAccounts,
+          ...reserveAccounts,
           deskSigner: deskKeypair.publicKey,
           payer: payerKeypair.publicKey,
           tokenProgram: new PublicKey("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"),
@@ -1588,6 +1588,7 @@ export function AcceptQuoteModal({
           paymentCurrencySol, // currency (0 = SOL, 1 = USDC)
           lockupSeconds, // lockup_secs
           agentCommissionBps, // agent_commission_bps
+          [], // allowlist_proof (public consignment)
         )
         .accounts({
           desk,
@@ -317,6 +317,7 @@ export interface SolanaConsignmentParams {
   isPrivate: boolean;
   maxPriceVolatilityBps: number;
   maxTimeToExecuteSeconds: number;
+  acceptedCurrencies?: number; // bitmask: 1 = SOL, 2 = USDC; defaults to both
 }
 
 /**
@@ -416,6 +417,7 @@ export async function createSolanaConsignment(
       params.isPrivate,
       params.maxPriceVolatilityBps,
       new anchor.BN(params.maxTimeToExecuteSeconds),
+      params.acceptedCurrencies ?? 3,
     )
     .accounts({
       desk,
@@ -559,6 +561,7 @@ export async function createSolanaOfferFromConsignment(
       params.paymentCurrency,
       lockupSeconds,
       params.agentCommissionBps,
+      [], // allowlist_proof (public consignment)
     )
     .accounts({
       desk,